@@ -1,5 +1,10 @@
-use clap::Parser;
-use osm_converter::sphere::{geometry::planet::Planet, graph::graph_generator::generate_network};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use osm_converter::sphere::{
+    geometry::planet::Planet,
+    graph::graph_generator::{
+        generate_network, Generator, GeneratorConfig, NeighborSelection, Placement,
+    },
+};
 
 /// Starts a routing service on localhost:3030/route
 #[derive(Parser, Debug)]
@@ -8,33 +13,97 @@ struct Args {
     /// Path of .fmi file
     #[arg(short, long)]
     input: String,
-    /// Path of .fmi file
+    /// Number of nodes of the grid, the visibility graph has a node for every coastline vertex
     #[arg(short, long)]
-    num_nodes: u32,
+    num_nodes: Option<u32>,
     /// Path of .fmi file
     #[arg(short, long)]
     gr_file: String,
     /// Path of .fmi file
     #[arg(short, long)]
     co_file: String,
+    /// Path of the binary .fmi file, which is only written if given
+    #[arg(long)]
+    output_network: Option<String>,
     /// Path of .fmi file
     #[arg(short, long)]
     output_geojson: String,
     /// Path of .png file
     #[arg(short, long)]
     output_image: String,
+    /// How the nodes and arcs are generated
+    #[arg(long, value_enum, default_value_t = Generator::Grid)]
+    generator: Generator,
+    /// How the nodes are placed on the sea
+    #[arg(long, value_enum, default_value_t = Placement::Random)]
+    placement: Placement,
+    /// How many times denser the nodes of the coastal placement are near coastlines
+    #[arg(long, default_value_t = 4.0)]
+    coast_factor: f64,
+    /// How the nodes a node is connected to are selected
+    #[arg(long, value_enum, default_value_t = NeighborSelection::Sectors)]
+    neighbors: NeighborSelection,
+    /// Number of equal sectors around a node, for the sectors selection
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    num_sectors: u32,
+    /// Number of nearest nodes a node is connected to, per sector for the sectors selection
+    #[arg(short, long, default_value_t = 1)]
+    k: u32,
+    /// Maximum length of an arc of the grid in meters
+    #[arg(long, default_value_t = 30_000.0)]
+    max_arc_length: f64,
+    /// Maximum length of an arc of the visibility graph in meters, unbounded if not given. Every
+    /// node is checked against all nodes within it, so unbounded takes quadratic time in the number
+    /// of coastline vertices
+    #[arg(long)]
+    visibility_radius: Option<f64>,
+    /// Tolerance in meters the coastlines are simplified with for the visibility graph
+    #[arg(long, default_value_t = 1_000.0)]
+    simplify_tolerance: f64,
+    /// Seed of the random placement of the nodes, a random seed is used if not given
+    #[arg(long)]
+    seed: Option<u64>,
 }
 
 fn main() {
     let args = Args::parse();
 
+    // the visibility graph has a node for every coastline vertex
+    let num_nodes = match (args.generator, args.num_nodes) {
+        (_, Some(num_nodes)) => num_nodes,
+        (Generator::Visibility, None) => 0,
+        (Generator::Grid, None) => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--num-nodes is required for the grid generator",
+            )
+            .exit(),
+    };
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    println!("using seed {}", seed);
+
     let planet = Planet::from_geojson_file(args.input.as_str()).unwrap();
 
+    let config = GeneratorConfig {
+        generator: args.generator,
+        num_nodes,
+        placement: args.placement,
+        coast_factor: args.coast_factor,
+        neighbors: args.neighbors,
+        num_sectors: args.num_sectors,
+        k: args.k,
+        radius: args.max_arc_length,
+        visibility_radius: args.visibility_radius,
+        simplify_tolerance: args.simplify_tolerance,
+        seed,
+    };
     generate_network(
-        args.num_nodes,
+        &config,
         &planet,
         args.gr_file.as_str(),
         args.co_file.as_str(),
+        args.output_network.as_deref(),
         args.output_geojson.as_str(),
         args.output_image.as_str(),
     );
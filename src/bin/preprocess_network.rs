@@ -0,0 +1,217 @@
+use std::{collections::BTreeMap, path::Path, process};
+
+use clap::{Parser, Subcommand};
+use osm_converter::sphere::{
+    graph::{
+        binary_graph::BinaryGraph,
+        error::GraphError,
+        graph::{read_graph, Fmi, GraphFormat},
+        mapped_graph::MappedGraph,
+        text_graph::TextGraph,
+    },
+    routing::{
+        adjacency_array::AdjacencyArray,
+        contraction_hierarchy::ContractionHierarchy,
+        hub_labels::HubLabels,
+        landmarks::{LandmarkSelection, Landmarks},
+    },
+    spatial_partition::kd_tree::KdTree,
+};
+
+/// Precomputes speedup data for the routing server
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path of the .fmi file in any format, see the convert command
+    #[arg(short, long, conflicts_with_all = ["gr_file", "co_file"])]
+    fmi_file: Option<String>,
+    /// Path of .gr file
+    #[arg(
+        short,
+        long,
+        required_unless_present = "fmi_file",
+        requires = "co_file"
+    )]
+    gr_file: Option<String>,
+    /// Path of .co file
+    #[arg(
+        short,
+        long,
+        required_unless_present = "fmi_file",
+        requires = "gr_file"
+    )]
+    co_file: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Builds a contraction hierarchy
+    Ch {
+        /// Path of .ch file, defaults to the graph file with the extension replaced
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Selects landmarks and computes their distances to every node
+    Alt {
+        /// Path of .alt file, defaults to the graph file with the extension replaced
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Number of landmarks
+        #[arg(short, long, default_value_t = 16)]
+        num_landmarks: u32,
+        /// Strategy used to place the landmarks
+        #[arg(short, long, value_enum, default_value_t = LandmarkSelection::Avoid)]
+        selection: LandmarkSelection,
+    },
+    /// Computes hub labels from the order of a contraction hierarchy
+    Hl {
+        /// Path of .hl file, defaults to the graph file with the extension replaced
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Path of an existing .ch file, the hierarchy is computed if none is given
+        #[arg(long)]
+        ch_file: Option<String>,
+    },
+    /// Converts the graph to another file format
+    Convert {
+        /// Path of the converted file
+        #[arg(short, long)]
+        output: String,
+        /// Format of the converted file
+        #[arg(long, value_enum, default_value_t = GraphFormat::Binary)]
+        format: GraphFormat,
+    },
+    /// Same as convert --format binary, kept for existing scripts
+    Fmi {
+        /// Path of .fmi file, defaults to the graph file with the extension replaced
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Same as convert --format mapped, kept for existing scripts
+    Mmap {
+        /// Path of .mmap file, defaults to the graph file with the extension replaced
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Builds a k-d tree of the nodes used to look up the nodes nearest to a coordinate
+    Kd {
+        /// Path of .kd file, defaults to the graph file with the extension replaced
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let (fmi, graph) = load_graph(&args).unwrap_or_else(|error| {
+        eprintln!("cannot load graph: {}", error);
+        process::exit(1);
+    });
+    // speedup data is written next to the graph by default
+    let graph_file = args.fmi_file.or(args.gr_file).unwrap();
+
+    let command = match args.command {
+        Command::Fmi { output } => Command::Convert {
+            output: output.unwrap_or_else(|| next_to(&graph_file, "fmi")),
+            format: GraphFormat::Binary,
+        },
+        Command::Mmap { output } => Command::Convert {
+            output: output.unwrap_or_else(|| next_to(&graph_file, "mmap")),
+            format: GraphFormat::Mapped,
+        },
+        command => command,
+    };
+
+    match command {
+        Command::Ch { output } => {
+            let output = output.unwrap_or_else(|| next_to(&graph_file, "ch"));
+            let contraction_hierarchy = ContractionHierarchy::from_graph(&graph);
+            println!("writing to {}", output);
+            contraction_hierarchy.to_file(output.as_str()).unwrap();
+        }
+        Command::Alt {
+            output,
+            num_landmarks,
+            selection,
+        } => {
+            let output = output.unwrap_or_else(|| next_to(&graph_file, "alt"));
+            let landmarks = Landmarks::new(&graph, num_landmarks, selection);
+            println!("writing to {}", output);
+            landmarks.to_file(output.as_str()).unwrap();
+        }
+        Command::Hl { output, ch_file } => {
+            let output = output.unwrap_or_else(|| next_to(&graph_file, "hl"));
+            let contraction_hierarchy = match ch_file {
+                Some(ch_file) => {
+                    let contraction_hierarchy = ContractionHierarchy::from_file(ch_file.as_str())
+                        .unwrap_or_else(|error| {
+                            eprintln!("cannot load {}: {}", ch_file, error);
+                            process::exit(1);
+                        });
+                    if contraction_hierarchy.graph_id() != graph.graph_id() {
+                        eprintln!(
+                            "{} was computed for a graph of {}, but the graph has {}",
+                            ch_file,
+                            contraction_hierarchy.graph_id(),
+                            graph.graph_id()
+                        );
+                        process::exit(1);
+                    }
+                    contraction_hierarchy
+                }
+                None => ContractionHierarchy::from_graph(&graph),
+            };
+            let hub_labels = HubLabels::from_contraction_hierarchy(&contraction_hierarchy);
+            println!("writing to {}", output);
+            hub_labels.to_file(output.as_str()).unwrap();
+        }
+        Command::Convert { output, format } => {
+            println!("writing to {}", output);
+            match format {
+                GraphFormat::Text => TextGraph::from_graph(&fmi.points, &graph)
+                    .to_file(output.as_str())
+                    .unwrap(),
+                GraphFormat::Binary => {
+                    let metadata = BTreeMap::from([("source".to_string(), graph_file.clone())]);
+                    BinaryGraph::from_graph(&fmi.points, graph, metadata)
+                        .to_file(output.as_str())
+                        .unwrap();
+                }
+                GraphFormat::Mapped => {
+                    MappedGraph::to_file(output.as_str(), &fmi.points, &graph).unwrap()
+                }
+            }
+        }
+        Command::Kd { output } => {
+            let output = output.unwrap_or_else(|| next_to(&graph_file, "kd"));
+            let kd_tree = KdTree::new(&fmi.points);
+            println!("writing to {}", output);
+            kd_tree.to_file(output.as_str()).unwrap();
+        }
+        Command::Fmi { .. } | Command::Mmap { .. } => unreachable!("replaced by convert"),
+    }
+}
+
+/// Returns the path with its extension replaced.
+fn next_to(path: &str, extension: &str) -> String {
+    Path::new(path)
+        .with_extension(extension)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Reads the graph from the .fmi file or from the .gr and .co files.
+fn load_graph(args: &Args) -> Result<(Fmi, AdjacencyArray), GraphError> {
+    match (&args.fmi_file, &args.gr_file, &args.co_file) {
+        (Some(fmi_file), _, _) => read_graph(fmi_file.as_str()),
+        (None, Some(gr_file), Some(co_file)) => {
+            let fmi = Fmi::from_gr_co_file(gr_file.as_str(), co_file.as_str())?;
+            let graph = AdjacencyArray::from_fmi(&fmi);
+            Ok((fmi, graph))
+        }
+        _ => unreachable!("clap requires either the .fmi or the .gr and .co files"),
+    }
+}
@@ -0,0 +1,916 @@
+use std::{error::Error, fmt, net::SocketAddr, process, sync};
+
+use clap::Parser;
+use geojson::FeatureCollection;
+use osm_converter::sphere::{
+    geometry::{linestring::Linestring, planet::Planet, point::Point},
+    graph::{
+        error::GraphError,
+        graph::{read_graph, validate_edge_lengths, Fmi, GraphFormat},
+        mapped_graph::MappedGraph,
+    },
+    routing::{
+        a_star::AStar,
+        adjacency_array::{self, AdjacencyArray, GraphId},
+        alternatives::{Alternatives, MAX_ALTERNATIVES},
+        bidirectional_dijkstra::BidirectionalDijkstra,
+        contraction_hierarchy::ContractionHierarchy,
+        dijkstra::Dijkstra,
+        distance_matrix::{many_to_many, many_to_many_dijkstra},
+        endpoint::{Endpoint, EndpointError},
+        graph_view::{Coordinates, GraphView},
+        heuristic::GreatCircle,
+        hub_labels::HubLabels,
+        isochrone::{travel_distance, Isochrone},
+        landmarks::Landmarks,
+        route::{PathFinding, Route},
+        smoothing::smooth,
+        tour::visiting_order,
+    },
+    spatial_partition::{kd_tree::KdTree, polygon_spatial_partition::PolygonSpatialPartition},
+};
+use serde_derive::{Deserialize, Serialize};
+use warp::Filter;
+
+/// Number of nearby nodes an exact endpoint is connected to. The searches without speedup data
+/// start from all candidates at once, but the speedup techniques search every pair of source and
+/// target candidates, so this is kept small.
+const ENDPOINT_CANDIDATES: usize = 4;
+
+/// Starts a routing service on localhost:3030/route
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path of the .fmi file, in the text, binary or memory mapped format
+    #[arg(
+        short,
+        long,
+        alias = "mapped-file",
+        short_alias = 'm',
+        conflicts_with_all = ["gr_file", "co_file"]
+    )]
+    fmi_file: Option<String>,
+    /// Path of .gr file
+    #[arg(
+        short,
+        long,
+        required_unless_present = "fmi_file",
+        requires = "co_file"
+    )]
+    gr_file: Option<String>,
+    /// Path of .co file
+    #[arg(
+        short,
+        long,
+        required_unless_present = "fmi_file",
+        requires = "gr_file"
+    )]
+    co_file: Option<String>,
+    /// Maximal length of an arc in meters, the graph is rejected if it has a longer one
+    #[arg(long)]
+    max_arc_length: Option<f64>,
+    /// Address the server binds to
+    #[arg(short, long, default_value = "127.0.0.1:3030")]
+    bind: SocketAddr,
+    /// Path of .ch file, enables the contraction hierarchy
+    #[arg(long)]
+    ch_file: Option<String>,
+    /// Path of .alt file, enables A* with landmarks
+    #[arg(long)]
+    alt_file: Option<String>,
+    /// Path of .hl file, enables hub labels
+    #[arg(long)]
+    hl_file: Option<String>,
+    /// Path of .kd file, the index of the nodes is built on startup if none is given
+    #[arg(long)]
+    kd_file: Option<String>,
+    /// Maximal number of sources times targets of a distance matrix
+    #[arg(long, default_value_t = 10_000)]
+    max_matrix_size: usize,
+    /// Maximal number of searches of the whole graph for a distance matrix or tour without a
+    /// contraction hierarchy or hub labels, one per source or target, whichever are fewer
+    #[arg(long, default_value_t = 32)]
+    max_matrix_searches: usize,
+    /// Path of the .geojson file of the planet, enables smoothing of routes
+    #[arg(long)]
+    planet: Option<String>,
+    /// Directory of the static frontend
+    #[arg(short, long, default_value = "public-html")]
+    public_html: String,
+}
+
+/// Body of a POST /route request, coordinates are given as [lon, lat].
+#[derive(Deserialize, Debug)]
+struct RouteRequest {
+    from: (f64, f64),
+    to: (f64, f64),
+    /// Defaults to the fastest algorithm available.
+    algorithm: Option<Algorithm>,
+    /// Replaces the zig-zag of the route by direct arcs, needs the planet to be loaded.
+    #[serde(default)]
+    smooth: bool,
+}
+
+/// Body of a POST /via request, the route visits the waypoints in the given order. Coordinates
+/// are given as [lon, lat].
+#[derive(Deserialize, Debug)]
+struct ViaRequest {
+    waypoints: Vec<(f64, f64)>,
+    /// Defaults to the fastest algorithm available.
+    algorithm: Option<Algorithm>,
+    /// Replaces the zig-zag of the route by direct arcs, needs the planet to be loaded.
+    #[serde(default)]
+    smooth: bool,
+}
+
+/// Body of a POST /tour request, the route visits every port in the order with the shortest
+/// distance. Ports are given as [lon, lat], start and end are indices into them.
+#[derive(Deserialize, Debug)]
+struct TourRequest {
+    ports: Vec<(f64, f64)>,
+    start: Option<usize>,
+    end: Option<usize>,
+    /// Defaults to the fastest algorithm available.
+    algorithm: Option<Algorithm>,
+    /// Replaces the zig-zag of the route by direct arcs, needs the planet to be loaded.
+    #[serde(default)]
+    smooth: bool,
+}
+
+/// Body of a POST /alternatives request, coordinates are given as [lon, lat].
+#[derive(Deserialize, Debug)]
+struct AlternativesRequest {
+    from: (f64, f64),
+    to: (f64, f64),
+    /// Maximal number of routes, defaults to 3 and must not exceed `MAX_ALTERNATIVES`.
+    k: Option<usize>,
+}
+
+/// Body of a POST /matrix request, coordinates are given as [lon, lat].
+#[derive(Deserialize, Debug)]
+struct MatrixRequest {
+    sources: Vec<(f64, f64)>,
+    targets: Vec<(f64, f64)>,
+}
+
+/// Response to a POST /matrix request, `distances[i][j]` is the distance from source i to target
+/// j in meters, or `null` if there is no route.
+#[derive(Serialize, Debug)]
+struct MatrixResponse {
+    distances: Vec<Vec<Option<u32>>>,
+}
+
+/// Body of a POST /isochrone request, the coordinate is given as [lon, lat]. The budget is
+/// either a distance in meters or a duration in hours at a speed in knots.
+#[derive(Deserialize, Debug)]
+struct IsochroneRequest {
+    from: (f64, f64),
+    distance: Option<f64>,
+    hours: Option<f64>,
+    knots: Option<f64>,
+    /// Also return every reachable node as a point.
+    #[serde(default)]
+    nodes: bool,
+}
+
+/// Response to a request that cannot be answered.
+#[derive(Serialize, Debug)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// The reason a well-formed request cannot be answered.
+#[derive(Debug)]
+enum RequestError {
+    Endpoint(EndpointError),
+    MatrixTooLarge {
+        size: usize,
+        max_size: usize,
+    },
+    TooManySearches {
+        searches: usize,
+        max_searches: usize,
+    },
+    TooManyAlternatives(usize),
+    MissingBudget,
+}
+
+impl From<EndpointError> for RequestError {
+    fn from(error: EndpointError) -> RequestError {
+        RequestError::Endpoint(error)
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequestError::Endpoint(error) => write!(f, "{}", error),
+            RequestError::MatrixTooLarge { size, max_size } => write!(
+                f,
+                "the matrix has {} entries, at most {} are allowed",
+                size, max_size
+            ),
+            RequestError::TooManySearches {
+                searches,
+                max_searches,
+            } => write!(
+                f,
+                "the matrix needs {} searches of the whole graph without speedup data, at most {} \
+                 are allowed",
+                searches, max_searches
+            ),
+            RequestError::TooManyAlternatives(k) => write!(
+                f,
+                "{} alternatives were requested, at most {} are allowed",
+                k, MAX_ALTERNATIVES
+            ),
+            RequestError::MissingBudget => {
+                write!(f, "either distance or hours and knots are required")
+            }
+        }
+    }
+}
+
+/// The search algorithm used to answer a request.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum Algorithm {
+    Dijkstra,
+    AStar,
+    BidirectionalDijkstra,
+    ContractionHierarchy,
+    Alt,
+    HubLabels,
+}
+
+/// A graph the server routes on, node ids are indices into its coordinates.
+trait RoutingGraph: GraphView + Coordinates + Send + Sync + 'static {
+    /// The identity the speedup data is checked against, see `AdjacencyArray::graph_id`.
+    fn graph_id(&self) -> GraphId;
+}
+
+/// A graph read into memory from the text or binary formats or from .gr and .co files.
+struct OwnedGraph {
+    points: Vec<Point>,
+    graph: AdjacencyArray,
+}
+
+impl GraphView for OwnedGraph {
+    type Edges<'a> = adjacency_array::Edges<'a>;
+
+    fn num_nodes(&self) -> u32 {
+        self.graph.num_nodes()
+    }
+
+    fn edges(&self, node: u32) -> adjacency_array::Edges<'_> {
+        self.graph.edges(node)
+    }
+}
+
+impl Coordinates for OwnedGraph {
+    fn num_points(&self) -> u32 {
+        self.points.len() as u32
+    }
+
+    fn point(&self, node: u32) -> Point {
+        self.points[node as usize]
+    }
+}
+
+impl RoutingGraph for OwnedGraph {
+    fn graph_id(&self) -> GraphId {
+        self.graph.graph_id()
+    }
+}
+
+/// A memory mapped graph is searched in place, so restarting the server does not copy it.
+impl RoutingGraph for MappedGraph {
+    fn graph_id(&self) -> GraphId {
+        MappedGraph::graph_id(self)
+    }
+}
+
+/// The graph as it was loaded, see `load_graph`.
+enum LoadedGraph {
+    Owned(OwnedGraph),
+    Mapped(MappedGraph),
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    println!("loading graph");
+    match load_graph(&args) {
+        Ok(LoadedGraph::Owned(graph)) => serve(args, graph).await,
+        Ok(LoadedGraph::Mapped(graph)) => serve(args, graph).await,
+        Err(error) => {
+            eprintln!("cannot load graph: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+/// Loads the speedup data for graph and answers requests until the server is stopped.
+async fn serve<G: RoutingGraph>(args: Args, graph: G) {
+    if let Some(max_arc_length) = args.max_arc_length {
+        if let Err(error) = validate_edge_lengths(&graph, max_arc_length) {
+            eprintln!("invalid graph: {}", error);
+            process::exit(1);
+        }
+    }
+    let graph_id = graph.graph_id();
+    let contraction_hierarchy = args.ch_file.map(|ch_file| {
+        load_speedup_data(
+            &ch_file,
+            graph_id,
+            ContractionHierarchy::from_file,
+            ContractionHierarchy::graph_id,
+        )
+    });
+    let landmarks = args.alt_file.map(|alt_file| {
+        load_speedup_data(
+            &alt_file,
+            graph_id,
+            Landmarks::from_file,
+            Landmarks::graph_id,
+        )
+    });
+    let hub_labels = args.hl_file.map(|hl_file| {
+        load_speedup_data(
+            &hl_file,
+            graph_id,
+            HubLabels::from_file,
+            HubLabels::graph_id,
+        )
+    });
+    let kd_tree = match args.kd_file {
+        Some(kd_file) => {
+            let kd_tree = KdTree::from_file(kd_file.as_str()).unwrap_or_else(|error| {
+                eprintln!("cannot load {}: {}", kd_file, error);
+                process::exit(1);
+            });
+            if !kd_tree.is_built_from(&graph) {
+                eprintln!(
+                    "{} was built for {} nodes at other coordinates than the {} nodes of the graph",
+                    kd_file,
+                    kd_tree.len(),
+                    graph.num_points()
+                );
+                process::exit(1);
+            }
+            kd_tree
+        }
+        None => KdTree::from_coordinates(&graph),
+    };
+    let planet_grid = args.planet.map(|planet| {
+        let planet = Planet::from_geojson_file(planet.as_str()).unwrap();
+        let mut planet_grid = PolygonSpatialPartition::new(100);
+        planet_grid.add_polygons(&planet.polygons);
+        planet_grid
+    });
+    let router = sync::Arc::new(Router {
+        graph,
+        contraction_hierarchy,
+        landmarks,
+        hub_labels,
+        kd_tree,
+        planet_grid,
+        max_matrix_size: args.max_matrix_size,
+        max_matrix_searches: args.max_matrix_searches,
+    });
+    println!("graph loaded");
+
+    let with_router = warp::any().map(move || router.clone());
+
+    let route = warp::path("route")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_router.clone())
+        .map(|request: RouteRequest, router: sync::Arc<Router<G>>| {
+            json_reply(router.route(&request))
+        });
+
+    let via = warp::path("via")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_router.clone())
+        .map(|request: ViaRequest, router: sync::Arc<Router<G>>| json_reply(router.via(&request)));
+
+    let tour = warp::path("tour")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_router.clone())
+        .map(|request: TourRequest, router: sync::Arc<Router<G>>| {
+            json_reply(router.tour(&request))
+        });
+
+    let alternatives = warp::path("alternatives")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_router.clone())
+        .map(
+            |request: AlternativesRequest, router: sync::Arc<Router<G>>| {
+                json_reply(router.alternatives(&request))
+            },
+        );
+
+    let matrix = warp::path("matrix")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_router.clone())
+        .map(|request: MatrixRequest, router: sync::Arc<Router<G>>| {
+            json_reply(router.matrix(&request))
+        });
+
+    let isochrone = warp::path("isochrone")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_router.clone())
+        .map(|request: IsochroneRequest, router: sync::Arc<Router<G>>| {
+            json_reply(router.isochrone(&request))
+        });
+
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_methods(vec!["GET", "POST"])
+        .allow_header("content-type");
+
+    let public_html = warp::fs::dir(args.public_html);
+
+    println!("listening on {}", args.bind);
+    warp::serve(
+        route
+            .or(via)
+            .or(tour)
+            .or(alternatives)
+            .or(matrix)
+            .or(isochrone)
+            .or(public_html)
+            .with(cors),
+    )
+    .run(args.bind)
+    .await;
+}
+
+/// The graph together with the optional speedup data, the optional planet used for collision
+/// checks and the limits of requests.
+struct Router<G> {
+    graph: G,
+    contraction_hierarchy: Option<ContractionHierarchy>,
+    landmarks: Option<Landmarks>,
+    hub_labels: Option<HubLabels>,
+    kd_tree: KdTree,
+    planet_grid: Option<PolygonSpatialPartition>,
+    max_matrix_size: usize,
+    max_matrix_searches: usize,
+}
+
+impl<G: RoutingGraph> Router<G> {
+    /// Returns the requested algorithm if its speedup data is loaded, otherwise the fastest
+    /// algorithm available.
+    fn algorithm(&self, requested: Option<Algorithm>) -> Algorithm {
+        let fastest = if self.hub_labels.is_some() {
+            Algorithm::HubLabels
+        } else if self.contraction_hierarchy.is_some() {
+            Algorithm::ContractionHierarchy
+        } else {
+            Algorithm::BidirectionalDijkstra
+        };
+
+        match requested {
+            Some(Algorithm::HubLabels) if self.hub_labels.is_none() => fastest,
+            Some(Algorithm::ContractionHierarchy) if self.contraction_hierarchy.is_none() => {
+                fastest
+            }
+            Some(Algorithm::Alt) if self.landmarks.is_none() => Algorithm::AStar,
+            Some(algorithm) => algorithm,
+            None => fastest,
+        }
+    }
+
+    fn path_finding(&self, algorithm: Algorithm) -> Box<dyn PathFinding + '_> {
+        match algorithm {
+            Algorithm::Dijkstra => Box::new(Dijkstra::new(&self.graph)),
+            Algorithm::AStar => Box::new(AStar::new(&self.graph, GreatCircle::new(&self.graph))),
+            Algorithm::BidirectionalDijkstra => Box::new(BidirectionalDijkstra::new(&self.graph)),
+            Algorithm::ContractionHierarchy => {
+                Box::new(self.contraction_hierarchy.as_ref().unwrap())
+            }
+            Algorithm::Alt => Box::new(AStar::new(&self.graph, self.landmarks.as_ref().unwrap())),
+            Algorithm::HubLabels => Box::new(self.hub_labels.as_ref().unwrap()),
+        }
+    }
+
+    /// Returns the node nearest to a [lon, lat] coordinate.
+    fn snap(&self, coordinate: (f64, f64)) -> Result<u32, RequestError> {
+        let coordinate = (wrap_longitude(coordinate.0), coordinate.1);
+        let endpoint = Endpoint::nearest_node(&self.graph, &self.kd_tree, coordinate)?;
+        Ok(endpoint.connections[0].0)
+    }
+
+    /// Converts points to a linestring, smoothed if requested and the planet is loaded.
+    fn linestring(&self, points: Vec<Point>, smooth_path: bool) -> Linestring {
+        match &self.planet_grid {
+            Some(planet_grid) if smooth_path => {
+                let smoothed = smooth(&points, planet_grid);
+                println!("smoothed {} to {} points", points.len(), smoothed.len());
+                Linestring::new(smoothed)
+            }
+            _ => Linestring::new(points),
+        }
+    }
+
+    /// Returns the endpoint at a [lon, lat] coordinate. If the planet is loaded, the route starts
+    /// exactly at the coordinate, otherwise at the nearest node.
+    fn endpoint(&self, coordinate: (f64, f64)) -> Result<Endpoint, EndpointError> {
+        let coordinate = (wrap_longitude(coordinate.0), coordinate.1);
+        match &self.planet_grid {
+            Some(planet_grid) => Endpoint::new(
+                &self.graph,
+                &self.kd_tree,
+                planet_grid,
+                coordinate,
+                ENDPOINT_CANDIDATES,
+            ),
+            None => Endpoint::nearest_node(&self.graph, &self.kd_tree, coordinate),
+        }
+    }
+
+    /// Returns the endpoints at [lon, lat] coordinates, see `endpoint`.
+    fn endpoints(&self, coordinates: &[(f64, f64)]) -> Result<Vec<Endpoint>, EndpointError> {
+        coordinates
+            .iter()
+            .map(|&coordinate| self.endpoint(coordinate))
+            .collect()
+    }
+
+    /// Returns one route per leg between consecutive endpoints, or `None` if any leg has no
+    /// route. The distances include the arcs between the endpoints and their nodes.
+    fn legs(&self, algorithm: Algorithm, endpoints: &[Endpoint]) -> Option<Vec<Route>> {
+        let path_finding = self.path_finding(algorithm);
+        endpoints
+            .windows(2)
+            .map(|leg| path_finding.shortest_path_between(&leg[0].connections, &leg[1].connections))
+            .collect()
+    }
+
+    /// Returns the points of the legs between consecutive endpoints, passing every endpoint
+    /// exactly.
+    fn points_along(&self, endpoints: &[Endpoint], legs: &[Route]) -> Vec<Point> {
+        let mut points = Vec::new();
+        for (endpoint, leg) in endpoints.iter().zip(legs) {
+            points.push(endpoint.point);
+            points.extend(leg.path.iter().map(|&node| self.graph.point(node)));
+        }
+        points.extend(endpoints.last().map(|endpoint| endpoint.point));
+        points.dedup();
+        points
+    }
+
+    fn route(&self, request: &RouteRequest) -> Result<FeatureCollection, RequestError> {
+        let endpoints = [self.endpoint(request.from)?, self.endpoint(request.to)?];
+
+        let algorithm = self.algorithm(request.algorithm);
+        let features = match self.legs(algorithm, &endpoints) {
+            Some(legs) => {
+                let route = &legs[0];
+                println!(
+                    "{:?}: route of {} m, settled {} nodes",
+                    algorithm, route.distance, route.settled
+                );
+                let points = self.points_along(&endpoints, &legs);
+                let linestring = self.linestring(points, request.smooth);
+                let mut feature = linestring.to_feature();
+                feature.set_property("distance", route.distance);
+                vec![feature]
+            }
+            None => Vec::new(),
+        };
+
+        Ok(FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        })
+    }
+
+    /// Returns the route along all waypoints as one feature, with its total length and the
+    /// length of every leg in meters as properties.
+    fn via(&self, request: &ViaRequest) -> Result<FeatureCollection, RequestError> {
+        let waypoints = self.endpoints(&request.waypoints)?;
+
+        let algorithm = self.algorithm(request.algorithm);
+        let features = match self.legs(algorithm, &waypoints) {
+            Some(legs) if !legs.is_empty() => {
+                let distance: u32 = legs.iter().map(|leg| leg.distance).sum();
+                println!(
+                    "{:?}: route of {} m over {} waypoints, settled {} nodes",
+                    algorithm,
+                    distance,
+                    waypoints.len(),
+                    legs.iter().map(|leg| leg.settled).sum::<u32>()
+                );
+                let points = self.points_along(&waypoints, &legs);
+                let legs: Vec<_> = legs.iter().map(|leg| leg.distance).collect();
+                let linestring = self.linestring(points, request.smooth);
+                let mut feature = linestring.to_feature();
+                feature.set_property("distance", distance);
+                feature.set_property("legs", legs);
+                vec![feature]
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        })
+    }
+
+    /// Returns the route along all ports in the best order found as one feature, with its total
+    /// length, the length of every leg and the order of the ports as properties.
+    fn tour(&self, request: &TourRequest) -> Result<FeatureCollection, RequestError> {
+        let size = request.ports.len() * request.ports.len();
+        if size > self.max_matrix_size {
+            return Err(RequestError::MatrixTooLarge {
+                size,
+                max_size: self.max_matrix_size,
+            });
+        }
+        let ports = self.endpoints(&request.ports)?;
+        let valid = |index: Option<usize>| index.iter().all(|&index| index < ports.len());
+        let order = if valid(request.start) && valid(request.end) {
+            let distances = self.endpoint_distances(&ports, &ports)?;
+            visiting_order(&distances, request.start, request.end)
+        } else {
+            None
+        };
+
+        let algorithm = self.algorithm(request.algorithm);
+        let features = order
+            .and_then(|order| {
+                let waypoints: Vec<_> = order.iter().map(|&index| ports[index].clone()).collect();
+                let legs = self.legs(algorithm, &waypoints)?;
+                let distance: u32 = legs.iter().map(|leg| leg.distance).sum();
+                println!(
+                    "{:?}: tour of {} m over {} ports",
+                    algorithm,
+                    distance,
+                    ports.len()
+                );
+
+                let points = self.points_along(&waypoints, &legs);
+                let legs: Vec<_> = legs.iter().map(|leg| leg.distance).collect();
+                let linestring = self.linestring(points, request.smooth);
+                let mut feature = linestring.to_feature();
+                feature.set_property("distance", distance);
+                feature.set_property("legs", legs);
+                feature.set_property("order", order);
+                Some(vec![feature])
+            })
+            .unwrap_or_default();
+
+        Ok(FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        })
+    }
+
+    /// Returns every alternative route as its own feature, with its length in meters and its
+    /// stretch relative to the shortest route as properties. The alternatives are searched
+    /// between the nodes the shortest route connects the endpoints to.
+    fn alternatives(
+        &self,
+        request: &AlternativesRequest,
+    ) -> Result<FeatureCollection, RequestError> {
+        let k = request.k.unwrap_or(3);
+        if k > MAX_ALTERNATIVES {
+            return Err(RequestError::TooManyAlternatives(k));
+        }
+        let endpoints = [self.endpoint(request.from)?, self.endpoint(request.to)?];
+
+        let shortest = Dijkstra::new(&self.graph)
+            .shortest_path_between(&endpoints[0].connections, &endpoints[1].connections);
+        let routes = match &shortest {
+            Some(shortest) => {
+                let source = shortest.path[0];
+                let target = *shortest.path.last().unwrap();
+                Alternatives::new(&self.graph).routes(source, target, k)
+            }
+            None => Vec::new(),
+        };
+        println!("found {} alternative routes", routes.len());
+
+        // length of the arcs between the endpoints and the nodes the routes start and end at
+        let offset = routes.first().map_or(0, |route| {
+            let connection = |endpoint: &Endpoint, node: u32| {
+                endpoint
+                    .connections
+                    .iter()
+                    .find(|&&(connection, _)| connection == node)
+                    .map_or(0, |&(_, offset)| offset)
+            };
+            connection(&endpoints[0], route.path[0])
+                + connection(&endpoints[1], *route.path.last().unwrap())
+        });
+        let shortest_distance = routes.first().map_or(0, |route| route.distance + offset);
+        let features = routes
+            .iter()
+            .map(|route| {
+                let points = self.points_along(&endpoints, std::slice::from_ref(route));
+                let linestring = Linestring::new(points);
+                let mut feature = linestring.to_feature();
+                let distance = route.distance + offset;
+                feature.set_property("distance", distance);
+                feature.set_property("stretch", distance as f64 / shortest_distance.max(1) as f64);
+                feature
+            })
+            .collect();
+
+        Ok(FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        })
+    }
+
+    fn matrix(&self, request: &MatrixRequest) -> Result<MatrixResponse, RequestError> {
+        let size = request.sources.len() * request.targets.len();
+        if size > self.max_matrix_size {
+            return Err(RequestError::MatrixTooLarge {
+                size,
+                max_size: self.max_matrix_size,
+            });
+        }
+
+        let snap = |coordinates: &[(f64, f64)]| -> Result<Vec<u32>, RequestError> {
+            coordinates.iter().map(|&c| self.snap(c)).collect()
+        };
+        let sources = snap(&request.sources)?;
+        let targets = snap(&request.targets)?;
+
+        Ok(MatrixResponse {
+            distances: self.distances(&sources, &targets)?,
+        })
+    }
+
+    /// Returns the distances between all source and target endpoints, each over the best pair of
+    /// their connections.
+    fn endpoint_distances(
+        &self,
+        sources: &[Endpoint],
+        targets: &[Endpoint],
+    ) -> Result<Vec<Vec<Option<u32>>>, RequestError> {
+        // (index of the endpoint, node, offset) of every connection
+        let connections = |endpoints: &[Endpoint]| -> Vec<(usize, u32, u32)> {
+            endpoints
+                .iter()
+                .enumerate()
+                .flat_map(|(index, endpoint)| {
+                    endpoint
+                        .connections
+                        .iter()
+                        .map(move |&(node, offset)| (index, node, offset))
+                })
+                .collect()
+        };
+        let nodes = |connections: &[(usize, u32, u32)]| -> Vec<u32> {
+            connections.iter().map(|&(_, node, _)| node).collect()
+        };
+        let source_connections = connections(sources);
+        let target_connections = connections(targets);
+        let distances = self.distances(&nodes(&source_connections), &nodes(&target_connections))?;
+
+        let mut matrix = vec![vec![None; targets.len()]; sources.len()];
+        for (&(i, _, source_offset), row) in source_connections.iter().zip(&distances) {
+            for (&(j, _, target_offset), distance) in target_connections.iter().zip(row) {
+                if let Some(distance) = distance {
+                    let distance = distance + source_offset + target_offset;
+                    let entry: &mut Option<u32> = &mut matrix[i][j];
+                    *entry = Some(entry.map_or(distance, |entry| entry.min(distance)));
+                }
+            }
+        }
+        Ok(matrix)
+    }
+
+    /// Returns the distances between all sources and targets with the fastest method available.
+    /// Without speedup data, every search covers the whole graph, so their number is limited.
+    fn distances(
+        &self,
+        sources: &[u32],
+        targets: &[u32],
+    ) -> Result<Vec<Vec<Option<u32>>>, RequestError> {
+        let distances = if let Some(hub_labels) = &self.hub_labels {
+            sources
+                .iter()
+                .map(|&source| {
+                    targets
+                        .iter()
+                        .map(|&target| hub_labels.distance(source, target))
+                        .collect()
+                })
+                .collect()
+        } else if let Some(contraction_hierarchy) = &self.contraction_hierarchy {
+            many_to_many(contraction_hierarchy, sources, targets)
+        } else {
+            let searches = sources.len().min(targets.len());
+            if searches > self.max_matrix_searches {
+                return Err(RequestError::TooManySearches {
+                    searches,
+                    max_searches: self.max_matrix_searches,
+                });
+            }
+            many_to_many_dijkstra(&self.graph, sources, targets)
+        };
+        Ok(distances)
+    }
+
+    /// Returns the reachable area, and the reachable nodes if requested.
+    fn isochrone(&self, request: &IsochroneRequest) -> Result<FeatureCollection, RequestError> {
+        let max_distance = match (request.distance, request.hours, request.knots) {
+            (Some(distance), _, _) => distance.round() as u32,
+            (None, Some(hours), Some(knots)) => travel_distance(hours, knots),
+            _ => return Err(RequestError::MissingBudget),
+        };
+        let source = self.snap(request.from)?;
+
+        let isochrone = Isochrone::new(&self.graph, &self.graph, source, max_distance);
+        println!(
+            "isochrone of {} m, reached {} nodes",
+            max_distance,
+            isochrone.nodes.len()
+        );
+        Ok(isochrone
+            .to_planet(&self.graph, request.nodes)
+            .to_feature_collection())
+    }
+}
+
+/// Replies with the result as JSON, or with the error and status 400.
+fn json_reply<T: serde::Serialize>(
+    result: Result<T, RequestError>,
+) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(value) => {
+            warp::reply::with_status(warp::reply::json(&value), warp::http::StatusCode::OK)
+        }
+        Err(error) => warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: error.to_string(),
+            }),
+            warp::http::StatusCode::BAD_REQUEST,
+        ),
+    }
+}
+
+/// Leaflet reports longitudes outside of [-180, 180] if the map was panned across the antimeridian.
+fn wrap_longitude(longitude: f64) -> f64 {
+    (longitude + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Reads speedup data from path and exits if it cannot be read or was computed for another graph.
+fn load_speedup_data<T>(
+    path: &str,
+    expected: GraphId,
+    from_file: fn(&str) -> Result<T, Box<dyn Error>>,
+    graph_id: fn(&T) -> GraphId,
+) -> T {
+    let data = from_file(path).unwrap_or_else(|error| {
+        eprintln!("cannot load {}: {}", path, error);
+        process::exit(1);
+    });
+    let found = graph_id(&data);
+    if found != expected {
+        eprintln!(
+            "{} was computed for a graph of {}, but the graph has {}",
+            path, found, expected
+        );
+        process::exit(1);
+    }
+    data
+}
+
+/// Reads the graph from the .fmi file or from the .gr and .co files. A memory mapped file is only
+/// opened and validated, the other formats are read into memory without their arcs.
+fn load_graph(args: &Args) -> Result<LoadedGraph, GraphError> {
+    let (fmi, graph) = match (&args.fmi_file, &args.gr_file, &args.co_file) {
+        (Some(fmi_file), _, _) => match GraphFormat::detect(fmi_file)? {
+            GraphFormat::Mapped => return Ok(LoadedGraph::Mapped(MappedGraph::open(fmi_file)?)),
+            GraphFormat::Text | GraphFormat::Binary => read_graph(fmi_file.as_str())?,
+        },
+        (None, Some(gr_file), Some(co_file)) => {
+            let fmi = Fmi::from_gr_co_file(gr_file.as_str(), co_file.as_str())?;
+            let graph = AdjacencyArray::from_fmi(&fmi);
+            (fmi, graph)
+        }
+        _ => unreachable!("clap requires either the .fmi or the .gr and .co files"),
+    };
+    Ok(LoadedGraph::Owned(OwnedGraph {
+        points: fmi.points,
+        graph,
+    }))
+}
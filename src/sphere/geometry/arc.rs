@@ -49,8 +49,7 @@ impl Arc {
     }
 
     /// Returns a point that is somwhere on the arc.
-    pub fn random_intermediate_point(&self) -> Point {
-        let mut rng = rand::thread_rng();
+    pub fn random_intermediate_point(&self, rng: &mut impl Rng) -> Point {
         let f = rng.gen_range(0.0..1.0);
         Point::from_n_vector(&((self.from.n_vector() * (1.0 - f) + self.to.n_vector() * f) / 2.0))
     }
@@ -145,6 +144,18 @@ impl Arc {
         from.angle(to)
     }
 
+    /// Returns the central angle between point and the nearest point of the arc in radians.
+    pub fn distance_to(&self, point: &Point) -> f64 {
+        // if the point lies in the lune between from_normal and to_normal, the nearest point of
+        // the great circle lies on the arc
+        if self.between_normals(point) {
+            return self.normal().dot(point.n_vector()).abs().min(1.0).asin();
+        }
+        let from = Arc::new(&self.from, point).central_angle();
+        let to = Arc::new(&self.to, point).central_angle();
+        from.min(to)
+    }
+
     /// Creates an arc from a GeoJSON-compatible vector. Note the GeoJSON order, which is longitude first.
     pub fn from_geojson_vec(vec: Vec<Vec<f64>>) -> Arc {
         Arc::new(
@@ -179,6 +190,28 @@ mod tests {
 
     use crate::sphere::geometry::{arc::Arc, point::Point};
 
+    #[test]
+    fn distance_to() {
+        let arc = Arc::new(
+            &Point::from_coordinate(0.0, 0.0),
+            &Point::from_coordinate(0.0, 10.0),
+        );
+        for (point, degrees) in [
+            (Point::from_coordinate(5.0, 5.0), 5.0),
+            (Point::from_coordinate(-2.0, 1.0), 2.0),
+            (Point::from_coordinate(0.0, 15.0), 5.0),
+            (Point::from_coordinate(0.0, -3.0), 3.0),
+            (Point::from_coordinate(0.0, 4.0), 0.0),
+        ] {
+            let distance = arc.distance_to(&point);
+            assert!(
+                (distance - (degrees as f64).to_radians()).abs() < 1e-10,
+                "distance was {}",
+                distance
+            );
+        }
+    }
+
     #[test]
     fn test_central_angle1() {
         let from = Point::from_coordinate(90.0, 0.0);
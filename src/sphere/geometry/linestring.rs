@@ -15,8 +15,8 @@ impl Linestring {
             // .flatten()
             .collect();
         let mut points: Vec<Point> = arcs.iter().map(|arc| *arc.from()).collect();
-        if !points.is_empty() {
-            points.push(*points.last().unwrap());
+        if let Some(last_arc) = arcs.last() {
+            points.push(*last_arc.to());
         }
 
         Linestring { points }
@@ -5,7 +5,7 @@ use std::{
     str::FromStr,
 };
 
-use geojson::{Feature, Value};
+use geojson::{Feature, FeatureCollection, Value};
 use image::{GrayImage, Luma};
 use imageproc::{drawing::draw_antialiased_line_segment_mut, pixelops::interpolate};
 use indicatif::ProgressIterator;
@@ -126,16 +126,17 @@ impl Planet {
         Ok(planet)
     }
 
+    /// Returns the same features as `to_geojson_str`, e.g. to be sent as JSON by the server.
+    pub fn to_feature_collection(&self) -> FeatureCollection {
+        FeatureCollection {
+            bbox: None,
+            features: self.features(),
+            foreign_members: None,
+        }
+    }
+
     pub fn to_geojson_str(&self) -> String {
-        let mut features = Vec::new();
-        features.extend(self.points.iter().map(|point| point.to_feature()));
-        features.extend(self.polygons.iter().map(|polygon| polygon.to_feature()));
-        features.extend(self.arcs.iter().map(|line| line.to_feature()));
-        features.extend(
-            self.linestrings
-                .iter()
-                .map(|linestring| linestring.to_feature()),
-        );
+        let features = self.features();
 
         let mut writer = String::new();
         writer += r#"{"type":"FeatureCollection","features":["#;
@@ -152,6 +153,19 @@ impl Planet {
         writer
     }
 
+    fn features(&self) -> Vec<Feature> {
+        let mut features = Vec::new();
+        features.extend(self.points.iter().map(|point| point.to_feature()));
+        features.extend(self.polygons.iter().map(|polygon| polygon.to_feature()));
+        features.extend(self.arcs.iter().map(|line| line.to_feature()));
+        features.extend(
+            self.linestrings
+                .iter()
+                .map(|linestring| linestring.to_feature()),
+        );
+        features
+    }
+
     pub fn to_geojson_file(&self, path: &str) {
         println!("writing to file");
         let mut features = Vec::new();
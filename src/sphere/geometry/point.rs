@@ -2,7 +2,10 @@ use std::{f64::consts::PI, fmt, hash::Hash};
 
 use geojson::{Feature, Geometry, Value};
 use nalgebra::Vector3;
-use rand::Rng;
+use rand::{
+    rngs::{StdRng, ThreadRng},
+    Rng, SeedableRng,
+};
 
 use super::arc::Arc;
 
@@ -29,13 +32,16 @@ impl fmt::Display for Point {
     }
 }
 
-pub struct PointGenerator {}
+/// An endless iterator of points uniformly distributed over the sphere, drawn from rng.
+pub struct PointGenerator<R: Rng = ThreadRng> {
+    rng: R,
+}
 
-impl Iterator for PointGenerator {
+impl<R: Rng> Iterator for PointGenerator<R> {
     type Item = Point;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Some(Point::random())
+        Some(Point::random_with(&mut self.rng))
     }
 }
 
@@ -47,7 +53,20 @@ impl Default for PointGenerator {
 
 impl PointGenerator {
     pub fn new() -> PointGenerator {
-        PointGenerator {}
+        PointGenerator::from_rng(rand::thread_rng())
+    }
+}
+
+impl PointGenerator<StdRng> {
+    /// Creates a generator that yields the same points for the same seed.
+    pub fn seeded(seed: u64) -> PointGenerator<StdRng> {
+        PointGenerator::from_rng(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> PointGenerator<R> {
+    pub fn from_rng(rng: R) -> PointGenerator<R> {
+        PointGenerator { rng }
     }
 }
 
@@ -102,7 +121,11 @@ impl Point {
     }
 
     pub fn random() -> Point {
-        let mut rng = rand::thread_rng();
+        Point::random_with(&mut rand::thread_rng())
+    }
+
+    /// Returns a point uniformly distributed over the sphere, drawn from rng.
+    pub fn random_with(rng: &mut impl Rng) -> Point {
         let y: f64 = rng.gen_range(-1.0..1.0);
         let lat_rad: f64 = y.asin();
         let lat: f64 = lat_rad.to_degrees();
@@ -200,7 +223,9 @@ pub fn radians_to_meter(radians: f64) -> f64 {
 mod tests {
     use std::f64::consts::PI;
 
-    use crate::sphere::geometry::point::{meters_to_radians, radians_to_meter, Point};
+    use crate::sphere::geometry::point::{
+        meters_to_radians, radians_to_meter, Point, PointGenerator,
+    };
 
     #[test]
     fn conversion_between_n_vector_and_coordinates() {
@@ -217,6 +242,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn seeded_generator_is_reproducible() {
+        let points: Vec<_> = PointGenerator::seeded(42).take(100).collect();
+        assert!(points == PointGenerator::seeded(42).take(100).collect::<Vec<_>>());
+        assert!(points != PointGenerator::seeded(43).take(100).collect::<Vec<_>>());
+    }
+
     #[test]
     fn meters_to_radians_test() {
         let m = 10_000_000.0; // should be arround 1/4 of earths circumference
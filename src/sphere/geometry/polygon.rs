@@ -2,7 +2,10 @@ use std::vec;
 
 use geojson::{Feature, Geometry, Value};
 
-use super::{arc::Arc, point::Point};
+use super::{
+    arc::Arc,
+    point::{meters_to_radians, Point},
+};
 
 #[derive(Clone)]
 pub struct Polygon {
@@ -21,6 +24,54 @@ impl Polygon {
             .collect()
     }
 
+    /// Returns the polygon simplified with the Douglas-Peucker algorithm. Only vertices of the
+    /// outline are kept, and no vertex is farther than tolerance meters from the simplified
+    /// outline.
+    pub fn simplify(&self, tolerance: f64) -> Polygon {
+        let outline = &self.outline;
+        if outline.len() <= 4 {
+            return self.clone();
+        }
+        let tolerance = meters_to_radians(tolerance);
+        let last = outline.len() - 1;
+
+        // the outline is closed, so it is split at the vertex farthest from the first one
+        let farthest = (1..last)
+            .max_by(|&i, &j| {
+                let angle = |k: usize| Arc::new(&outline[0], &outline[k]).central_angle();
+                angle(i).total_cmp(&angle(j))
+            })
+            .unwrap();
+        let mut keep = vec![false; outline.len()];
+        keep[0] = true;
+        keep[farthest] = true;
+        keep[last] = true;
+
+        let mut ranges = vec![(0, farthest), (farthest, last)];
+        while let Some((first, last)) = ranges.pop() {
+            let arc = Arc::new(&outline[first], &outline[last]);
+            let farthest = (first + 1..last)
+                .map(|i| (i, arc.distance_to(&outline[i])))
+                .max_by(|x, y| x.1.total_cmp(&y.1));
+            if let Some((i, distance)) = farthest {
+                if distance > tolerance {
+                    keep[i] = true;
+                    ranges.push((first, i));
+                    ranges.push((i, last));
+                }
+            }
+        }
+
+        Polygon::new(
+            outline
+                .iter()
+                .zip(keep)
+                .filter(|&(_, keep)| keep)
+                .map(|(point, _)| *point)
+                .collect(),
+        )
+    }
+
     pub fn intersections(&self, line: &Arc) -> Vec<Point> {
         self.outline
             .windows(2)
@@ -59,3 +110,35 @@ impl Polygon {
         self.to_feature().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::geometry::{point::Point, polygon::Polygon};
+
+    #[test]
+    fn simplify() {
+        // a square with ten vertices on every side
+        let corners = [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)];
+        let outline: Vec<_> = corners
+            .windows(2)
+            .flat_map(|side| {
+                let ((lat0, lon0), (lat1, lon1)) = (side[0], side[1]);
+                (0..10).map(move |i| {
+                    let f = i as f64 / 10.0;
+                    Point::from_coordinate(lat0 + f * (lat1 - lat0), lon0 + f * (lon1 - lon0))
+                })
+            })
+            .chain([Point::from_coordinate(0.0, 0.0)])
+            .collect();
+        let square = Polygon::new(outline);
+
+        let simplified = square.simplify(1_000.0);
+        assert_eq!(simplified.outline.len(), 5);
+        assert!(corners.iter().all(|&(lat, lon)| simplified
+            .outline
+            .contains(&Point::from_coordinate(lat, lon))));
+        // the side along the latitude of one degree is no great circle, it bends by a few meters
+        let len = square.simplify(1.0).outline.len();
+        assert!(5 < len && len < 41, "{}", len);
+    }
+}
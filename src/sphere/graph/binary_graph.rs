@@ -0,0 +1,305 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::sphere::{
+    geometry::{arc::Arc, point::Point},
+    graph::{error::GraphError, graph::Fmi},
+    routing::adjacency_array::AdjacencyArray,
+};
+
+/// First bytes of every binary graph file.
+pub const MAGIC: [u8; 4] = *b"FMIB";
+
+/// Version of the file layout, increased with every incompatible change.
+pub const VERSION: u32 = 1;
+
+/// A graph stored in a single binary file. The file starts with a header of the magic bytes, the
+/// version and a checksum of the payload, followed by the payload, which is the graph serialized
+/// with bincode.
+///
+/// Like the .gr files written by `Fmi::to_gr_co_file`, the edges are symmetric: every arc is
+/// stored in both directions.
+#[derive(Serialize, Deserialize)]
+pub struct BinaryGraph {
+    /// Free form information about the graph, e.g. the parameters it was generated with.
+    pub metadata: BTreeMap<String, String>,
+    /// (latitude, longitude) of every node in degrees.
+    coordinates: Vec<(f64, f64)>,
+    edges: AdjacencyArray,
+}
+
+/// The reason a file cannot be read as a binary graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryGraphError {
+    /// The file does not start with the magic bytes.
+    NotABinaryGraph,
+    /// The file was written with another version of the layout.
+    UnsupportedVersion(u32),
+    /// The payload does not match the checksum of the header, e.g. as the file is truncated.
+    ChecksumMismatch,
+    /// The edges do not match the nodes, e.g. the offsets decrease or a head is not a node.
+    Inconsistent,
+}
+
+impl fmt::Display for BinaryGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryGraphError::NotABinaryGraph => write!(f, "not a binary graph file"),
+            BinaryGraphError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported version {}, expected version {}",
+                version, VERSION
+            ),
+            BinaryGraphError::ChecksumMismatch => write!(f, "checksum mismatch"),
+            BinaryGraphError::Inconsistent => {
+                write!(f, "offsets or heads do not match the nodes and edges")
+            }
+        }
+    }
+}
+
+impl Error for BinaryGraphError {}
+
+impl BinaryGraph {
+    pub fn from_fmi(fmi: &Fmi, metadata: BTreeMap<String, String>) -> BinaryGraph {
+        let edges = AdjacencyArray::from_edges(fmi.points.len() as u32, &fmi.symmetric_edges());
+        BinaryGraph::from_graph(&fmi.points, edges, metadata)
+    }
+
+    /// Creates a binary graph from edges whose weights are kept as they are, node ids are the
+    /// indices of points.
+    pub fn from_graph(
+        points: &[Point],
+        edges: AdjacencyArray,
+        metadata: BTreeMap<String, String>,
+    ) -> BinaryGraph {
+        BinaryGraph {
+            metadata,
+            coordinates: points
+                .iter()
+                .map(|point| (point.latitude(), point.longitude()))
+                .collect(),
+            edges,
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<BinaryGraph, GraphError> {
+        let invalid = |source| GraphError::Binary {
+            path: path.to_string(),
+            source,
+        };
+        let mut reader = BufReader::new(File::open(path).map_err(GraphError::io(path))?);
+
+        let mut magic = [0; 4];
+        let mut version = [0; 4];
+        let mut checksum = [0; 8];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| invalid(BinaryGraphError::NotABinaryGraph))?;
+        if magic != MAGIC {
+            return Err(invalid(BinaryGraphError::NotABinaryGraph));
+        }
+        reader
+            .read_exact(&mut version)
+            .and_then(|_| reader.read_exact(&mut checksum))
+            .map_err(|_| invalid(BinaryGraphError::ChecksumMismatch))?;
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            return Err(invalid(BinaryGraphError::UnsupportedVersion(version)));
+        }
+
+        let mut payload = Vec::new();
+        reader
+            .read_to_end(&mut payload)
+            .map_err(GraphError::io(path))?;
+        if fnv1a(&payload) != u64::from_le_bytes(checksum) {
+            return Err(invalid(BinaryGraphError::ChecksumMismatch));
+        }
+
+        let graph: BinaryGraph =
+            bincode::deserialize(&payload).map_err(|source| GraphError::Decode {
+                path: path.to_string(),
+                source,
+            })?;
+        // the searches index by offsets and heads without checking them
+        if !graph.edges.is_consistent()
+            || graph.edges.num_nodes() as usize != graph.coordinates.len()
+        {
+            return Err(invalid(BinaryGraphError::Inconsistent));
+        }
+        Ok(graph)
+    }
+
+    pub fn to_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let payload = bincode::serialize(self)?;
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&fnv1a(&payload).to_le_bytes())?;
+        writer.write_all(&payload)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn to_fmi(&self) -> Fmi {
+        let points: Vec<_> = self
+            .coordinates
+            .iter()
+            .map(|&(lat, lon)| Point::from_coordinate(lat, lon))
+            .collect();
+        let arcs = (0..self.edges.num_nodes())
+            .flat_map(|tail| {
+                let points = &points;
+                self.edges
+                    .edges(tail)
+                    .map(move |(head, _)| Arc::new(&points[tail as usize], &points[head as usize]))
+            })
+            .collect();
+
+        Fmi { points, arcs }
+    }
+
+    /// Returns the edges, node ids are the indices of the points of `to_fmi`.
+    pub fn adjacency_array(&self) -> &AdjacencyArray {
+        &self.edges
+    }
+
+    pub fn into_adjacency_array(self) -> AdjacencyArray {
+        self.edges
+    }
+}
+
+impl Fmi {
+    pub fn from_binary_file(path: &str) -> Result<Fmi, GraphError> {
+        Ok(BinaryGraph::from_file(path)?.to_fmi())
+    }
+
+    pub fn to_binary_file(
+        &self,
+        path: &str,
+        metadata: BTreeMap<String, String>,
+    ) -> Result<(), Box<dyn Error>> {
+        BinaryGraph::from_fmi(self, metadata).to_file(path)
+    }
+}
+
+/// The 64 bit FNV-1a hash, which is fast and good enough to detect corrupted files.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, fs};
+
+    use crate::sphere::{
+        geometry::{arc::Arc, point::Point},
+        graph::{
+            binary_graph::{BinaryGraph, BinaryGraphError},
+            error::GraphError,
+            graph::Fmi,
+        },
+        routing::adjacency_array::AdjacencyArray,
+    };
+
+    #[test]
+    fn round_trip() {
+        let points = vec![
+            Point::from_coordinate(0.0, 0.0),
+            Point::from_coordinate(0.0, 0.1),
+            Point::from_coordinate(0.1, 0.1),
+        ];
+        let arcs = vec![
+            Arc::new(&points[0], &points[1]),
+            Arc::new(&points[1], &points[2]),
+            Arc::new(&points[2], &points[1]),
+        ];
+        let fmi = Fmi { points, arcs };
+        let metadata = BTreeMap::from([("num_nodes".to_string(), "3".to_string())]);
+
+        let path = std::env::temp_dir().join("binary_graph_round_trip.fmi");
+        let path = path.to_str().unwrap();
+        fmi.to_binary_file(path, metadata.clone()).unwrap();
+
+        let binary_graph = BinaryGraph::from_file(path).unwrap();
+        assert_eq!(binary_graph.metadata, metadata);
+        assert_eq!(binary_graph.adjacency_array().num_edges(), 4);
+        let loaded = binary_graph.to_fmi();
+        assert_eq!(loaded.points.len(), 3);
+        assert!(loaded
+            .points
+            .iter()
+            .zip(&fmi.points)
+            .all(|(a, b)| a.is_approximately_equal(b)));
+        assert_eq!(loaded.arcs.len(), 4);
+
+        // flip a bit of the payload
+        let mut bytes = fs::read(path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(path, &bytes).unwrap();
+        assert!(matches!(
+            BinaryGraph::from_file(path),
+            Err(GraphError::Binary {
+                source: BinaryGraphError::ChecksumMismatch,
+                ..
+            })
+        ));
+
+        bytes[4] = 99;
+        fs::write(path, &bytes).unwrap();
+        assert!(matches!(
+            BinaryGraph::from_file(path),
+            Err(GraphError::Binary {
+                source: BinaryGraphError::UnsupportedVersion(99),
+                ..
+            })
+        ));
+
+        // a head that is not a node, written with a valid checksum
+        let edges = AdjacencyArray::from_csr(vec![0, 1, 1, 1], vec![5], vec![1]);
+        BinaryGraph::from_graph(&fmi.points, edges, BTreeMap::new())
+            .to_file(path)
+            .unwrap();
+        assert!(matches!(
+            BinaryGraph::from_file(path),
+            Err(GraphError::Binary {
+                source: BinaryGraphError::Inconsistent,
+                ..
+            })
+        ));
+
+        // fewer nodes in the edges than coordinates
+        let edges = AdjacencyArray::from_csr(vec![0, 1, 2], vec![1, 0], vec![1, 1]);
+        BinaryGraph::from_graph(&fmi.points, edges, BTreeMap::new())
+            .to_file(path)
+            .unwrap();
+        assert!(matches!(
+            BinaryGraph::from_file(path),
+            Err(GraphError::Binary {
+                source: BinaryGraphError::Inconsistent,
+                ..
+            })
+        ));
+
+        fs::write(path, "p sp 3 4").unwrap();
+        assert!(matches!(
+            BinaryGraph::from_file(path),
+            Err(GraphError::Binary {
+                source: BinaryGraphError::NotABinaryGraph,
+                ..
+            })
+        ));
+        fs::remove_file(path).unwrap();
+    }
+}
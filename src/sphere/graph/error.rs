@@ -0,0 +1,187 @@
+use std::{error::Error, fmt, io, str::FromStr};
+
+use crate::sphere::geometry::point::Point;
+
+use super::{binary_graph::BinaryGraphError, mapped_graph::MappedGraphError};
+
+/// The reason a graph cannot be loaded or is invalid.
+#[derive(Debug)]
+pub enum GraphError {
+    /// The file cannot be opened or read.
+    Io { path: String, source: io::Error },
+    /// A line of a text file cannot be parsed.
+    Parse {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    /// The header of a binary graph file is invalid.
+    Binary {
+        path: String,
+        source: BinaryGraphError,
+    },
+    /// The layout of a memory mapped graph file is invalid.
+    Mapped {
+        path: String,
+        source: MappedGraphError,
+    },
+    /// The payload of a binary graph file cannot be decoded.
+    Decode {
+        path: String,
+        source: bincode::Error,
+    },
+    /// An arc is longer than allowed, found by `Fmi::validate_arc_lengths`.
+    ArcTooLong {
+        arc: usize,
+        meters: f64,
+        max_meters: f64,
+    },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::Io { path, source } => write!(f, "{}: {}", path, source),
+            GraphError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path, line, message),
+            GraphError::Binary { path, source } => write!(f, "{}: {}", path, source),
+            GraphError::Mapped { path, source } => write!(f, "{}: {}", path, source),
+            GraphError::Decode { path, source } => write!(f, "{}: {}", path, source),
+            GraphError::ArcTooLong {
+                arc,
+                meters,
+                max_meters,
+            } => write!(
+                f,
+                "arc {} is {:.0} m long, at most {:.0} m are allowed",
+                arc, meters, max_meters
+            ),
+        }
+    }
+}
+
+impl Error for GraphError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GraphError::Io { source, .. } => Some(source),
+            GraphError::Binary { source, .. } => Some(source),
+            GraphError::Mapped { source, .. } => Some(source),
+            GraphError::Decode { source, .. } => Some(source),
+            GraphError::Parse { .. } | GraphError::ArcTooLong { .. } => None,
+        }
+    }
+}
+
+impl GraphError {
+    /// Returns a function wrapping an io error of the file at path, to be used with `map_err`.
+    pub fn io(path: &str) -> impl FnOnce(io::Error) -> GraphError + '_ {
+        move |source| GraphError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub fn parse(path: &str, line: usize, message: impl Into<String>) -> GraphError {
+        GraphError::Parse {
+            path: path.to_string(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Parses a whitespace separated field of a line of a text file.
+pub(crate) fn parse_field<T: FromStr>(
+    field: Option<&str>,
+    path: &str,
+    line: usize,
+) -> Result<T, GraphError> {
+    let field = field.ok_or_else(|| GraphError::parse(path, line, "missing field"))?;
+    field
+        .parse()
+        .map_err(|_| GraphError::parse(path, line, format!("cannot parse '{}'", field)))
+}
+
+/// Creates a point, checking that the coordinate lies within the valid ranges.
+pub(crate) fn parse_point(
+    latitude: f64,
+    longitude: f64,
+    path: &str,
+    line: usize,
+) -> Result<Point, GraphError> {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(GraphError::parse(
+            path,
+            line,
+            format!("illegal coordinate {} {}", latitude, longitude),
+        ));
+    }
+    Ok(Point::from_coordinate(latitude, longitude))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::sphere::graph::{error::GraphError, graph::Fmi};
+
+    #[test]
+    fn gr_co_errors() {
+        let directory = std::env::temp_dir();
+        let gr_path = directory.join("graph_error_gr_co_errors.gr");
+        let co_path = directory.join("graph_error_gr_co_errors.co");
+        let (gr_path, co_path) = (gr_path.to_str().unwrap(), co_path.to_str().unwrap());
+
+        fs::write(co_path, "p aux sp co 2\nv 0 10.0 20.0\nv 1 10.1 20.0\n").unwrap();
+        fs::write(gr_path, "p sp 2 2\na 0 1 11119\na 1 0 11119\n").unwrap();
+        let fmi = Fmi::from_gr_co_file(gr_path, co_path).unwrap();
+        assert_eq!(fmi.arcs.len(), 2);
+        assert!(fmi.validate_arc_lengths(30_000.0).is_ok());
+        assert!(matches!(
+            fmi.validate_arc_lengths(10_000.0),
+            Err(GraphError::ArcTooLong { arc: 0, .. })
+        ));
+
+        fs::write(gr_path, "p sp 2 2\na 0 1 11119\na 1 2 11119\n").unwrap();
+        let error = Fmi::from_gr_co_file(gr_path, co_path).err().unwrap();
+        assert!(matches!(error, GraphError::Parse { line: 3, .. }));
+        assert!(error.to_string().ends_with(":3: unknown node 2"));
+
+        fs::write(co_path, "p aux sp co 2\nv 0 10.0 20.0\nv 1 ten 20.0\n").unwrap();
+        let error = Fmi::from_gr_co_file(gr_path, co_path).err().unwrap();
+        assert!(error.to_string().ends_with(":3: cannot parse 'ten'"));
+
+        fs::write(co_path, "p aux sp co 2\nv 0 10.0 20.0\nv 1 100.0 20.0\n").unwrap();
+        let error = Fmi::from_gr_co_file(gr_path, co_path).err().unwrap();
+        assert!(error.to_string().ends_with(":3: illegal coordinate 100 20"));
+
+        fs::write(co_path, "p aux sp co 2\nv 0 10.0 20.0\nv 0 10.1 20.0\n").unwrap();
+        let error = Fmi::from_gr_co_file(gr_path, co_path).err().unwrap();
+        assert!(error.to_string().ends_with(":3: duplicate node 0"));
+
+        fs::write(co_path, "p aux sp co 3\nv 0 10.0 20.0\nv 1 10.1 20.0\n").unwrap();
+        let error = Fmi::from_gr_co_file(gr_path, co_path).err().unwrap();
+        assert!(error.to_string().ends_with(":1: expected 3 nodes, found 2"));
+
+        fs::write(co_path, "p aux sp co 2\nv 0 10.0 20.0\nv 1 10.1 20.0\n").unwrap();
+        fs::write(gr_path, "p sp 3 2\na 0 1 11119\na 1 0 11119\n").unwrap();
+        let error = Fmi::from_gr_co_file(gr_path, co_path).err().unwrap();
+        assert!(matches!(error, GraphError::Parse { line: 1, .. }));
+        assert!(error.to_string().ends_with(":1: expected 3 nodes, found 2"));
+
+        // older files counted every arc once in the header but listed both directions
+        fs::write(gr_path, "p sp 2 1\na 0 1 11119\na 1 0 11119\n").unwrap();
+        let fmi = Fmi::from_gr_co_file(gr_path, co_path).unwrap();
+        assert_eq!(fmi.arcs.len(), 2);
+
+        fs::remove_file(co_path).unwrap();
+        assert!(matches!(
+            Fmi::from_gr_co_file(gr_path, co_path),
+            Err(GraphError::Io { .. })
+        ));
+        fs::remove_file(gr_path).unwrap();
+    }
+}
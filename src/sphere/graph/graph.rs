@@ -1,67 +1,213 @@
 use std::{
     collections::HashMap,
     fs::File,
-    io::{BufRead, BufReader, BufWriter, Write},
+    io::{BufRead, BufReader, BufWriter, Read, Write},
     usize,
 };
 
-use crate::sphere::geometry::{
-    arc::Arc,
-    planet::Planet,
-    point::{radians_to_meter, Point},
+use clap::ValueEnum;
+
+use crate::sphere::{
+    geometry::{
+        arc::Arc,
+        planet::Planet,
+        point::{radians_to_meter, Point},
+    },
+    graph::{
+        binary_graph::{self, BinaryGraph},
+        error::{parse_field, parse_point, GraphError},
+        mapped_graph::{self, MappedGraph},
+        text_graph::TextGraph,
+    },
+    routing::{
+        adjacency_array::{arc_weight, AdjacencyArray},
+        graph_view::{Coordinates, GraphView},
+    },
 };
 
+/// The formats a graph can be stored in as a single file.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum GraphFormat {
+    /// The FMI text format of the course tools.
+    Text,
+    /// The checksummed binary format of `BinaryGraph`.
+    Binary,
+    /// The flat layout of `MappedGraph`.
+    Mapped,
+}
+
+impl GraphFormat {
+    /// Detects the format of a file by its first bytes, every file without the magic bytes of a
+    /// binary format is taken to be text.
+    pub fn detect(path: &str) -> Result<GraphFormat, GraphError> {
+        let mut start = Vec::new();
+        File::open(path)
+            .and_then(|file| file.take(8).read_to_end(&mut start))
+            .map_err(GraphError::io(path))?;
+        if start.starts_with(&binary_graph::MAGIC) {
+            Ok(GraphFormat::Binary)
+        } else if start.starts_with(&mapped_graph::MAGIC) {
+            Ok(GraphFormat::Mapped)
+        } else {
+            Ok(GraphFormat::Text)
+        }
+    }
+}
+
+/// Reads a graph in any single file format together with its adjacency array. The edges of the
+/// binary formats are taken as stored, the ones of the text format are built from its costs.
+/// A memory mapped graph is copied as well, use `MappedGraph::open` to search it in place, as
+/// the server does.
+pub fn read_graph(path: &str) -> Result<(Fmi, AdjacencyArray), GraphError> {
+    match GraphFormat::detect(path)? {
+        GraphFormat::Text => {
+            let text_graph = TextGraph::from_file(path)?;
+            Ok((text_graph.to_fmi(), text_graph.adjacency_array()))
+        }
+        GraphFormat::Binary => {
+            let binary_graph = BinaryGraph::from_file(path)?;
+            Ok((binary_graph.to_fmi(), binary_graph.into_adjacency_array()))
+        }
+        GraphFormat::Mapped => {
+            let mapped_graph = MappedGraph::open(path)?;
+            Ok((mapped_graph.to_fmi(), mapped_graph.to_adjacency_array()))
+        }
+    }
+}
+
+/// Same as `Fmi::validate_arc_lengths`, but for a graph that is searched in place, e.g. a
+/// `MappedGraph`. The arc of the error is the index of the edge in the order of the tails.
+pub fn validate_edge_lengths(
+    graph: &(impl GraphView + Coordinates + ?Sized),
+    max_meters: f64,
+) -> Result<(), GraphError> {
+    let mut arc = 0;
+    for tail in 0..GraphView::num_nodes(graph) {
+        let point = graph.point(tail);
+        for (head, _) in graph.edges(tail) {
+            let meters = radians_to_meter(Arc::new(&point, &graph.point(head)).central_angle());
+            if meters > max_meters {
+                return Err(GraphError::ArcTooLong {
+                    arc,
+                    meters,
+                    max_meters,
+                });
+            }
+            arc += 1;
+        }
+    }
+    Ok(())
+}
+
+/// A graph as points and the arcs between them. Nearest nodes are looked up with
+/// `KdTree::nearest` on a tree built from `points`.
 pub struct Fmi {
     pub points: Vec<Point>,
     pub arcs: Vec<Arc>,
 }
 
 impl Fmi {
-    pub fn from_gr_co_file(gr_path: &str, co_path: &str) -> Fmi {
+    pub fn from_gr_co_file(gr_path: &str, co_path: &str) -> Result<Fmi, GraphError> {
         let mut arcs = Vec::new();
         let mut points = HashMap::new();
 
-        //
-        let co_reader = BufReader::new(File::open(co_path).unwrap());
-        let co_lines = co_reader.lines();
-
-        co_lines.for_each(|line| {
-            let line = line.unwrap();
-            let line_sections: Vec<_> = line.split_whitespace().into_iter().collect();
-            if let Some(first_line_section) = line_sections.first() {
-                if first_line_section == &"v" {
-                    let id: u32 = line_sections.get(1).unwrap().parse().unwrap();
-                    let lat: f64 = line_sections.get(2).unwrap().parse().unwrap();
-                    let lon: f64 = line_sections.get(3).unwrap().parse().unwrap();
-                    points.insert(id, Point::from_coordinate(lat, lon));
+        // p aux sp co nodes
+        // v id latitude longitude
+        let mut node_header = None;
+        let co_reader = BufReader::new(File::open(co_path).map_err(GraphError::io(co_path))?);
+        for (index, line) in co_reader.lines().enumerate() {
+            let line = line.map_err(GraphError::io(co_path))?;
+            let mut line_sections = line.split_whitespace();
+            let number = index + 1;
+            match line_sections.next() {
+                Some("p") => {
+                    let nodes: usize = parse_field(line_sections.nth(3), co_path, number)?;
+                    node_header = Some((nodes, number));
                 }
+                Some("v") => {
+                    let id: u32 = parse_field(line_sections.next(), co_path, number)?;
+                    let lat: f64 = parse_field(line_sections.next(), co_path, number)?;
+                    let lon: f64 = parse_field(line_sections.next(), co_path, number)?;
+                    let point = parse_point(lat, lon, co_path, number)?;
+                    if points.insert(id, point).is_some() {
+                        return Err(GraphError::parse(
+                            co_path,
+                            number,
+                            format!("duplicate node {}", id),
+                        ));
+                    }
+                }
+                _ => {}
             }
-        });
+        }
+        if let Some((nodes, number)) = node_header {
+            if nodes != points.len() {
+                return Err(GraphError::parse(
+                    co_path,
+                    number,
+                    format!("expected {} nodes, found {}", nodes, points.len()),
+                ));
+            }
+        }
 
-        //
-        let gr_reader = BufReader::new(File::open(gr_path).unwrap());
-        let gr_lines = gr_reader.lines();
-
-        gr_lines.for_each(|line| {
-            let line = line.unwrap();
-            let line_sections: Vec<_> = line.split_whitespace().into_iter().collect();
-            if let Some(first_line_section) = line_sections.first() {
-                if first_line_section == &"a" {
-                    let tail: u32 = line_sections.get(1).unwrap().parse().unwrap();
-                    let head: u32 = line_sections.get(2).unwrap().parse().unwrap();
-                    let _weight: u32 = line_sections.get(3).unwrap().parse().unwrap();
-                    let arc = Arc::new(&points.get(&tail).unwrap(), &points.get(&head).unwrap());
-                    assert!(radians_to_meter(arc.central_angle()) <= 30_000.0);
-                    arcs.push(arc);
+        // p sp nodes arcs
+        // a tail head weight
+        // the arc count is not checked, older files counted every arc once but listed both
+        // directions of it
+        let gr_reader = BufReader::new(File::open(gr_path).map_err(GraphError::io(gr_path))?);
+        for (index, line) in gr_reader.lines().enumerate() {
+            let line = line.map_err(GraphError::io(gr_path))?;
+            let mut line_sections = line.split_whitespace();
+            let number = index + 1;
+            match line_sections.next() {
+                Some("p") => {
+                    let nodes: usize = parse_field(line_sections.nth(1), gr_path, number)?;
+                    if nodes != points.len() {
+                        return Err(GraphError::parse(
+                            gr_path,
+                            number,
+                            format!("expected {} nodes, found {}", nodes, points.len()),
+                        ));
+                    }
                 }
+                Some("a") => {
+                    let tail: u32 = parse_field(line_sections.next(), gr_path, number)?;
+                    let head: u32 = parse_field(line_sections.next(), gr_path, number)?;
+                    let _weight: u32 = parse_field(line_sections.next(), gr_path, number)?;
+                    let point = |id: u32| {
+                        points.get(&id).ok_or_else(|| {
+                            GraphError::parse(gr_path, number, format!("unknown node {}", id))
+                        })
+                    };
+                    arcs.push(Arc::new(point(tail)?, point(head)?));
+                }
+                _ => {}
             }
-        });
+        }
 
         let mut points: Vec<_> = points.into_iter().collect();
         points.sort_unstable_by_key(|(id, _)| *id);
         let points = points.into_iter().map(|(_, point)| point).collect();
 
-        Fmi { points, arcs }
+        Ok(Fmi { points, arcs })
+    }
+
+    /// Checks that no arc is longer than max_meters, e.g. the radius the graph was generated with.
+    pub fn validate_arc_lengths(&self, max_meters: f64) -> Result<(), GraphError> {
+        match self
+            .arcs
+            .iter()
+            .map(|arc| radians_to_meter(arc.central_angle()))
+            .enumerate()
+            .find(|&(_, meters)| meters > max_meters)
+        {
+            Some((arc, meters)) => Err(GraphError::ArcTooLong {
+                arc,
+                meters,
+                max_meters,
+            }),
+            None => Ok(()),
+        }
     }
 
     pub fn to_gr_co_file(&self, gr_path: &str, co_path: &str) {
@@ -71,20 +217,11 @@ impl Fmi {
             point_id_map.insert(point, i);
         }
 
-        let mut arc_map: HashMap<(u32, u32), u32> = HashMap::new();
-        self.arcs.iter().for_each(|arc| {
-            // srcIDX trgIDX cost type maxspeed
-            let source = *point_id_map.get(arc.from()).unwrap() as u32;
-            let target = *point_id_map.get(arc.to()).unwrap() as u32;
-            let cost = radians_to_meter(arc.central_angle()).round() as u32;
-            arc_map.insert((source, target), cost);
-            arc_map.insert((target, source), cost);
-        });
-
-        // write arcs
+        // write arcs, sorted so that the same graph always gives the same file
+        let edges = self.symmetric_edges();
         let mut gr_writer = BufWriter::new(File::create(gr_path).unwrap());
-        writeln!(gr_writer, "p sp {} {}", self.points.len(), self.arcs.len()).unwrap();
-        arc_map.iter().for_each(|((tail, head), weight)| {
+        writeln!(gr_writer, "p sp {} {}", self.points.len(), edges.len()).unwrap();
+        edges.iter().for_each(|(tail, head, weight)| {
             writeln!(gr_writer, "a {} {} {}", tail, head, weight).unwrap();
         });
         gr_writer.flush().unwrap();
@@ -109,26 +246,40 @@ impl Fmi {
         co_writer.flush().unwrap();
     }
 
-    pub fn to_planet(&self) -> Planet {
-        let mut planet = Planet::new();
-        planet.arcs = self.arcs.clone();
-        planet
+    /// Reads a graph in any single file format, see `GraphFormat`.
+    pub fn from_file(path: &str) -> Result<Fmi, GraphError> {
+        Ok(read_graph(path)?.0)
     }
 
-    pub fn nearest(&self, lon: f64, lat: f64) -> u32 {
-        let point = Point::from_coordinate(lat, lon);
-        self.points
+    /// Returns every arc in both directions as sorted (tail, head, weight) edges without
+    /// duplicates, as written to the .gr file.
+    pub fn symmetric_edges(&self) -> Vec<(u32, u32, u32)> {
+        let ids: HashMap<_, _> = self
+            .points
             .iter()
             .enumerate()
-            .min_by(|(_, a), (_, b)| {
-                let a = Arc::new(a, &point);
-                let b = Arc::new(b, &point);
-                a.central_angle().partial_cmp(&b.central_angle()).unwrap()
+            .map(|(id, point)| (*point, id as u32))
+            .collect();
+
+        let mut edges: Vec<_> = self
+            .arcs
+            .iter()
+            .flat_map(|arc| {
+                let tail = ids[arc.from()];
+                let head = ids[arc.to()];
+                let weight = arc_weight(arc);
+                [(tail, head, weight), (head, tail, weight)]
             })
-            .map(|(i, _)| i)
-            .unwrap()
-            .try_into()
-            .unwrap()
+            .collect();
+        edges.sort_unstable();
+        edges.dedup_by_key(|&mut (tail, head, _)| (tail, head));
+        edges
+    }
+
+    pub fn to_planet(&self) -> Planet {
+        let mut planet = Planet::new();
+        planet.arcs = self.arcs.clone();
+        planet
     }
 
     pub fn id_to_point(&self, id: u32) -> Point {
@@ -1,7 +1,11 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::f64::consts::PI;
 use std::time::Instant;
 
-use indicatif::ProgressIterator;
+use clap::ValueEnum;
+use indicatif::{ParallelProgressIterator, ProgressIterator};
+use nalgebra::Vector3;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rayon::prelude::*;
 
 use crate::sphere::geometry::arc::Arc;
@@ -10,27 +14,179 @@ use crate::sphere::geometry::point::{meters_to_radians, radians_to_meter, Point,
 use crate::sphere::graph::graph::Fmi;
 use crate::sphere::spatial_partition::point_spatial_partition::PointSpatialPartition;
 use crate::sphere::spatial_partition::polygon_spatial_partition::PolygonSpatialPartition;
-use crate::sphere::spatial_partition::tiling::ConvecQuadrilateral;
+
+/// How the nodes and arcs of a network are generated.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Generator {
+    /// Nodes placed on the sea, see `Placement`, each connected to its neighbors, see
+    /// `NeighborSelection`.
+    Grid,
+    /// The visibility graph of the vertices of the simplified coastlines, every two nodes within
+    /// the visibility radius, by default any two, are connected if the arc between them does not
+    /// cross a coastline.
+    Visibility,
+}
+
+impl Generator {
+    fn name(&self) -> &'static str {
+        match self {
+            Generator::Grid => "grid",
+            Generator::Visibility => "visibility",
+        }
+    }
+}
+
+/// How the nodes of a network are placed on the sea.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Placement {
+    /// Points drawn uniformly at random, the density varies from place to place.
+    Random,
+    /// The points of a Fibonacci lattice, which are spaced almost evenly.
+    Fibonacci,
+    /// The vertices of a subdivided icosahedron, which form a mesh of nearly equal triangles.
+    Icosahedral,
+    /// Points drawn at random, denser near winding coastlines such as fjords and archipelagos.
+    Coastal,
+}
+
+impl Placement {
+    fn name(&self) -> &'static str {
+        match self {
+            Placement::Random => "random",
+            Placement::Fibonacci => "fibonacci",
+            Placement::Icosahedral => "icosahedral",
+            Placement::Coastal => "coastal",
+        }
+    }
+}
+
+/// How the nodes a node is connected to are selected among the nodes within the radius.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum NeighborSelection {
+    /// The k nearest nodes in each of a number of equal sectors around the node, so that every
+    /// node has neighbors in all directions.
+    Sectors,
+    /// The k nearest nodes regardless of their direction.
+    Nearest,
+}
+
+impl NeighborSelection {
+    fn name(&self) -> &'static str {
+        match self {
+            NeighborSelection::Sectors => "sectors",
+            NeighborSelection::Nearest => "nearest",
+        }
+    }
+}
+
+/// The parameters of a generated network.
+#[derive(Clone, Debug)]
+pub struct GeneratorConfig {
+    pub generator: Generator,
+    /// The number of nodes of the grid, the visibility graph has a node for every vertex.
+    pub num_nodes: u32,
+    pub placement: Placement,
+    /// How many times denser the nodes of the coastal placement are near coastlines than on the
+    /// open sea.
+    pub coast_factor: f64,
+    pub neighbors: NeighborSelection,
+    /// The number of sectors of `NeighborSelection::Sectors`, the first one starts at north.
+    pub num_sectors: u32,
+    /// The number of nearest nodes a node is connected to, per sector for
+    /// `NeighborSelection::Sectors`.
+    pub k: u32,
+    /// The maximum length of an arc of the grid in meters.
+    pub radius: f64,
+    /// The maximum length of an arc of the visibility graph in meters, unbounded if not given.
+    /// Every node is checked for a collision with the coastlines against every other node within
+    /// it, so an unbounded radius takes time quadratic in the number of coastline vertices.
+    pub visibility_radius: Option<f64>,
+    /// The tolerance in meters the coastlines are simplified with for the visibility graph, see
+    /// `Polygon::simplify`.
+    pub simplify_tolerance: f64,
+    /// Seed of the random placement and of the midpoints of the planet grid, the same seed gives
+    /// the same network.
+    pub seed: u64,
+}
+
+impl GeneratorConfig {
+    /// The maximum length of an arc in meters of the chosen generator.
+    pub fn max_arc_length(&self) -> f64 {
+        match self.generator {
+            Generator::Grid => self.radius,
+            // no two points on the sphere are further apart than half its circumference
+            Generator::Visibility => self.visibility_radius.unwrap_or(radians_to_meter(PI)),
+        }
+    }
+}
+
+/// The density of coastline arcs from which on the coastal placement is coast_factor times denser
+/// than on the open sea, one arc per square of 10 km.
+const COASTAL_ARC_DENSITY: f64 = 1.0 / (10_000.0 * 10_000.0);
+
+/// The distance in meters the nodes of the visibility graph are moved from their coastline vertex
+/// onto the sea, as arcs touching a coastline count as colliding. A point counts as touching an
+/// arc if the detour over it is below a meter, which for 1 km holds only for arcs longer than
+/// 2000 km.
+const VERTEX_OFFSET: f64 = 1_000.0;
 
 pub fn generate_network(
-    num_nodes: u32,
+    config: &GeneratorConfig,
     planet: &Planet,
     gr_path: &str,
     co_path: &str,
+    network_path: Option<&str>,
     planet_path: &str,
     image_path: &str,
 ) {
+    let radius = config.max_arc_length();
     let start = Instant::now();
-    let planet_grid = generate_planet_grid(planet);
-    let points = generate_points(num_nodes, &planet_grid);
+    let planet_grid = generate_planet_grid(planet, config.seed);
+    let points = match config.generator {
+        Generator::Grid => generate_points(config, &planet_grid),
+        Generator::Visibility => generate_visibility_points(planet, &planet_grid, config),
+    };
 
     println!("took {:?}", start.elapsed());
     let point_grid = generate_point_grid(&points);
-    let arcs = generate_arcs(&points, &point_grid, &planet_grid, 30_000.0);
+    let arcs = match config.generator {
+        Generator::Grid => generate_arcs(&points, &point_grid, &planet_grid, config),
+        Generator::Visibility => {
+            generate_visibility_arcs(&points, &point_grid, &planet_grid, radius)
+        }
+    };
 
     let gr = Fmi { points, arcs };
     gr.to_gr_co_file(gr_path, co_path);
-    let test = Fmi::from_gr_co_file(gr_path, co_path);
+    if let Some(network_path) = network_path {
+        let mut metadata = BTreeMap::from([
+            ("generator".to_string(), config.generator.name().to_string()),
+            ("num_nodes".to_string(), gr.points.len().to_string()),
+            ("radius".to_string(), radius.to_string()),
+            ("seed".to_string(), config.seed.to_string()),
+        ]);
+        match config.generator {
+            Generator::Grid => {
+                let placement = config.placement.name().to_string();
+                metadata.insert("placement".to_string(), placement);
+                metadata.insert("neighbors".to_string(), config.neighbors.name().to_string());
+                metadata.insert("k".to_string(), config.k.to_string());
+                if config.neighbors == NeighborSelection::Sectors {
+                    metadata.insert("num_sectors".to_string(), config.num_sectors.to_string());
+                }
+                if config.placement == Placement::Coastal {
+                    metadata.insert("coast_factor".to_string(), config.coast_factor.to_string());
+                }
+            }
+            Generator::Visibility => {
+                let tolerance = config.simplify_tolerance.to_string();
+                metadata.insert("simplify_tolerance".to_string(), tolerance);
+            }
+        }
+        gr.to_binary_file(network_path, metadata).unwrap();
+    }
+    let test = Fmi::from_gr_co_file(gr_path, co_path).unwrap();
+    test.validate_arc_lengths(radius).unwrap();
     assert_eq!(gr.points[0].latitude(), test.points[0].latitude());
     let fmi_planet = gr.to_planet();
 
@@ -38,16 +194,171 @@ pub fn generate_network(
     fmi_planet.to_geojson_file(planet_path);
 }
 
-fn generate_points(how_many: u32, planet_grid: &PolygonSpatialPartition) -> Vec<Point> {
+fn generate_points(config: &GeneratorConfig, planet_grid: &PolygonSpatialPartition) -> Vec<Point> {
     println!("generating points");
-    PointGenerator::new()
-        .filter(|point| point.latitude() >= -82.0)
-        .filter(|point| !planet_grid.is_on_polygon(point))
-        .take(how_many as usize)
-        .progress_count(how_many as u64)
+    let how_many = config.num_nodes;
+    match config.placement {
+        Placement::Random => PointGenerator::seeded(config.seed)
+            .filter(|point| is_sea(point, planet_grid))
+            .take(how_many as usize)
+            .progress_count(how_many as u64)
+            .collect(),
+        Placement::Fibonacci => lattice_points(how_many, planet_grid, fibonacci_lattice),
+        Placement::Icosahedral => lattice_points(how_many, planet_grid, |size| {
+            // an icosahedron subdivided with frequency f has 10 f^2 + 2 vertices
+            let frequency = ((size.saturating_sub(2) as f64 / 10.0).sqrt().ceil() as usize).max(1);
+            icosahedral_lattice(frequency)
+        }),
+        Placement::Coastal => coastal_points(config, planet_grid),
+    }
+}
+
+/// Returns random points of the sea whose density is up to coast_factor times higher where the
+/// coastline is dense, see `PolygonSpatialPartition::arc_density`. Uniform candidates are kept
+/// with a probability proportional to the density at their place.
+fn coastal_points(config: &GeneratorConfig, planet_grid: &PolygonSpatialPartition) -> Vec<Point> {
+    let coast_factor = config.coast_factor.max(1.0);
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    std::iter::repeat_with(|| {
+        (
+            Point::random_with(&mut rng),
+            rng.gen_range(0.0..coast_factor),
+        )
+    })
+    .filter(|(point, _)| is_sea(point, planet_grid))
+    .filter(|(point, draw)| {
+        let coastal = (planet_grid.arc_density(point) / COASTAL_ARC_DENSITY).min(1.0);
+        *draw < 1.0 + (coast_factor - 1.0) * coastal
+    })
+    .map(|(point, _)| point)
+    .take(config.num_nodes as usize)
+    .progress_count(config.num_nodes as u64)
+    .collect()
+}
+
+/// Nodes are only placed on the sea and not on the ice shelves around Antarctica.
+fn is_sea(point: &Point, planet_grid: &PolygonSpatialPartition) -> bool {
+    point.latitude() >= -82.0 && !planet_grid.is_on_polygon(point)
+}
+
+/// Returns how_many points of the sea from a lattice, which returns at least the given number of
+/// points. The lattice is grown until enough of its points are on the sea, the few surplus points
+/// are dropped evenly.
+fn lattice_points(
+    how_many: u32,
+    planet_grid: &PolygonSpatialPartition,
+    lattice: impl Fn(usize) -> Vec<Point>,
+) -> Vec<Point> {
+    let how_many = how_many as usize;
+    let mut size = how_many;
+    loop {
+        let candidates = lattice(size);
+        let points: Vec<_> = candidates
+            .par_iter()
+            .progress_count(candidates.len() as u64)
+            .filter(|point| is_sea(point, planet_grid))
+            .cloned()
+            .collect();
+        if points.len() >= how_many {
+            return (0..how_many)
+                .map(|i| points[i * points.len() / how_many])
+                .collect();
+        }
+        assert!(!points.is_empty(), "no point of the lattice is on the sea");
+
+        // the share of the sea in this lattice estimates the size needed, with a small margin
+        size = (candidates.len() as f64 * how_many as f64 / points.len() as f64 * 1.01).ceil()
+            as usize;
+    }
+}
+
+/// Returns n points of a Fibonacci lattice, which lie on a spiral from the north to the south
+/// pole with successive points a golden angle apart.
+pub fn fibonacci_lattice(n: usize) -> Vec<Point> {
+    let golden_angle = PI * (3.0 - 5f64.sqrt());
+    (0..n)
+        .map(|i| {
+            let z = 1.0 - (2 * i + 1) as f64 / n as f64;
+            let longitude = (i as f64 * golden_angle).rem_euclid(2.0 * PI) - PI;
+            Point::from_coordinate(z.asin().to_degrees(), longitude.to_degrees())
+        })
         .collect()
 }
 
+/// Returns the vertices of an icosahedron whose edges are divided into frequency parts, projected
+/// onto the sphere. These are 10 frequency^2 + 2 points.
+pub fn icosahedral_lattice(frequency: usize) -> Vec<Point> {
+    let phi = (1.0 + 5f64.sqrt()) / 2.0;
+    let vertices: Vec<Vector3<f64>> = [
+        (-1.0, phi, 0.0),
+        (1.0, phi, 0.0),
+        (-1.0, -phi, 0.0),
+        (1.0, -phi, 0.0),
+        (0.0, -1.0, phi),
+        (0.0, 1.0, phi),
+        (0.0, -1.0, -phi),
+        (0.0, 1.0, -phi),
+        (phi, 0.0, -1.0),
+        (phi, 0.0, 1.0),
+        (-phi, 0.0, -1.0),
+        (-phi, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vector3::new(x, y, z))
+    .collect();
+    let faces: [[usize; 3]; 20] = [
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+    let f = frequency as f64;
+    let point = |n_vector: Vector3<f64>| Point::from_n_vector(&n_vector.normalize());
+
+    // every vertex, edge and face contributes the points that lie only on it
+    let mut points: Vec<_> = vertices.iter().map(|&vertex| point(vertex)).collect();
+    let mut edges: Vec<_> = faces
+        .iter()
+        .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    for (a, b) in edges {
+        for k in 1..frequency {
+            let k = k as f64;
+            points.push(point(vertices[a] * (f - k) + vertices[b] * k));
+        }
+    }
+    for [a, b, c] in faces {
+        for i in 1..frequency {
+            for j in 1..frequency - i {
+                let (i, j) = (i as f64, j as f64);
+                points.push(point(
+                    vertices[a] * (f - i - j) + vertices[b] * i + vertices[c] * j,
+                ));
+            }
+        }
+    }
+    points
+}
+
 fn generate_point_grid(points: &Vec<Point>) -> PointSpatialPartition {
     println!("generating point grid");
     let mut point_grid = PointSpatialPartition::new_root(10);
@@ -55,9 +366,9 @@ fn generate_point_grid(points: &Vec<Point>) -> PointSpatialPartition {
     point_grid
 }
 
-fn generate_planet_grid(planet: &Planet) -> PolygonSpatialPartition {
+fn generate_planet_grid(planet: &Planet, seed: u64) -> PolygonSpatialPartition {
     println!("generating planet grid");
-    let mut planet_grid = PolygonSpatialPartition::new(100);
+    let mut planet_grid = PolygonSpatialPartition::with_seed(100, seed);
     planet_grid.add_polygons(&planet.polygons);
     planet_grid
 }
@@ -66,63 +377,352 @@ fn generate_arcs(
     points: &Vec<Point>,
     point_grid: &PointSpatialPartition,
     planet_grid: &PolygonSpatialPartition,
-    radius: f64,
+    config: &GeneratorConfig,
 ) -> Vec<Arc> {
     println!("generating arcs");
+    let k = config.k as usize;
+    let num_sectors = config.num_sectors.max(1) as usize;
+    let sector_angle = 2.0 * PI / num_sectors as f64;
+
+    // par_iter keeps the order of the points, so the arcs are the same in every run
     points
-        .iter()
+        .par_iter()
         .progress()
-        .par_bridge()
         .map(|point| {
-            [
-                ur(point, radius, 2.0),
-                ur(point, radius, 4.0),
-                ur(point, radius, 6.0),
-                ur(point, radius, 8.0),
-            ]
-            .iter()
-            .filter_map(|polygon| {
-                let mut local_points = point_grid.get_points(polygon);
-
-                local_points.sort_unstable_by(|x, y| {
-                    Arc::new(point, y)
-                        .central_angle()
-                        .total_cmp(&Arc::new(point, x).central_angle())
-                });
-
-                // if last point is point, take second
-                if let Some(last_point) = local_points.last() {
-                    if last_point == point {
-                        local_points.pop();
-                    }
-                }
-                if let Some(target) = local_points.pop() {
-                    let arc = Arc::new(point, &target);
-                    if radians_to_meter(arc.central_angle()) <= radius {
-                        return Some(arc);
-                    }
+            let candidates = point_grid
+                .within_distance(point, config.radius)
+                .into_iter()
+                .filter(|other| other != point);
+            let targets: Vec<_> = match config.neighbors {
+                NeighborSelection::Nearest => candidates.take(k).collect(),
+                NeighborSelection::Sectors => {
+                    let mut taken = vec![0; num_sectors];
+                    candidates
+                        .filter(|other| {
+                            let bearing = Arc::new(point, other).initial_bearing();
+                            let sector = ((bearing / sector_angle) as usize).min(num_sectors - 1);
+                            taken[sector] += 1;
+                            taken[sector] <= k
+                        })
+                        .collect()
                 }
+            };
 
-                None
-            })
-            .filter(|arc| !planet_grid.check_collision(arc))
-            .collect::<Vec<_>>()
+            targets
+                .iter()
+                .map(|target| Arc::new(point, target))
+                .filter(|arc| !planet_grid.check_collision(arc))
+                .collect::<Vec<_>>()
+        })
+        .flatten()
+        .collect()
+}
+
+/// Returns a node next to every vertex of the simplified coastlines, moved onto the sea along
+/// the bisector of the angle at the vertex. Vertices without sea on either side of the bisector,
+/// e.g. in very narrow straits, are left out.
+fn generate_visibility_points(
+    planet: &Planet,
+    planet_grid: &PolygonSpatialPartition,
+    config: &GeneratorConfig,
+) -> Vec<Point> {
+    println!("generating points");
+    let offset = meters_to_radians(VERTEX_OFFSET);
+    let points: Vec<_> = planet
+        .polygons
+        .par_iter()
+        .progress()
+        .flat_map_iter(|polygon| {
+            let outline = polygon.simplify(config.simplify_tolerance).outline;
+            // the outline is closed, its last vertex is the first one
+            let len = outline.len().saturating_sub(1);
+            (0..len)
+                .filter(|&i| outline[i].latitude() >= -82.0)
+                .filter_map(|i| {
+                    let vertex = &outline[i];
+                    let previous = Arc::new(vertex, &outline[(i + len - 1) % len]);
+                    let next = Arc::new(vertex, &outline[(i + 1) % len]);
+                    let bisector = (previous.initial_bearing() + next.initial_bearing()) / 2.0;
+                    [bisector, bisector + PI]
+                        .into_iter()
+                        .map(|bearing| Point::destination_point(vertex, bearing, offset))
+                        .find(|point| is_sea(point, planet_grid))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // two coastlines may share a vertex
+    let mut seen = HashSet::new();
+    points
+        .into_iter()
+        .filter(|point| seen.insert(*point))
+        .collect()
+}
+
+/// Connects every two points at most radius meters apart whose arc does not cross a coastline.
+/// Every pair is checked once, the arcs are written in both directions anyway.
+fn generate_visibility_arcs(
+    points: &Vec<Point>,
+    point_grid: &PointSpatialPartition,
+    planet_grid: &PolygonSpatialPartition,
+    radius: f64,
+) -> Vec<Arc> {
+    println!("generating arcs");
+    let ids: HashMap<_, _> = points
+        .iter()
+        .enumerate()
+        .map(|(id, point)| (*point, id))
+        .collect();
+
+    points
+        .par_iter()
+        .enumerate()
+        .progress()
+        .map(|(id, point)| {
+            point_grid
+                .within_distance(point, radius)
+                .into_iter()
+                .filter(|other| ids[other] > id)
+                .map(|other| Arc::new(point, &other))
+                .filter(|arc| !planet_grid.check_collision(arc))
+                .collect::<Vec<_>>()
         })
         .flatten()
         .collect()
 }
 
-fn ur(point: &Point, radius: f64, start: f64) -> ConvecQuadrilateral {
-    let cloned_point = *point;
-    ConvecQuadrilateral::new(&vec![
-        cloned_point,
-        Point::destination_point(point, (start) / 4.0 * PI, meters_to_radians(radius)),
-        Point::destination_point(
-            point,
-            (start - 1.0) / 4.0 * PI,
-            meters_to_radians((radius.powi(2) + radius.powi(2)).sqrt()),
-        ),
-        Point::destination_point(point, (start - 2.0) / 4.0 * PI, meters_to_radians(radius)),
-        cloned_point,
-    ])
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, f64::consts::PI};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::sphere::{
+        geometry::{
+            arc::Arc,
+            planet::Planet,
+            point::{radians_to_meter, Point},
+            polygon::Polygon,
+        },
+        graph::graph_generator::{
+            fibonacci_lattice, generate_arcs, generate_point_grid, generate_points,
+            generate_visibility_arcs, generate_visibility_points, icosahedral_lattice, Generator,
+            GeneratorConfig, NeighborSelection, Placement,
+        },
+        spatial_partition::polygon_spatial_partition::PolygonSpatialPartition,
+    };
+
+    fn min_max_spacing(points: &[Point]) -> (f64, f64) {
+        let nearest: Vec<_> = points
+            .iter()
+            .map(|point| {
+                points
+                    .iter()
+                    .filter(|other| *other != point)
+                    .map(|other| Arc::new(point, other).central_angle())
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .collect();
+        (
+            nearest.iter().cloned().fold(f64::INFINITY, f64::min),
+            nearest.iter().cloned().fold(0.0, f64::max),
+        )
+    }
+
+    #[test]
+    fn lattices_are_even() {
+        for frequency in 1..=8 {
+            let points = icosahedral_lattice(frequency);
+            assert_eq!(points.len(), 10 * frequency * frequency + 2);
+            assert_eq!(points.iter().collect::<HashSet<_>>().len(), points.len());
+        }
+        let (min, max) = min_max_spacing(&icosahedral_lattice(8));
+        assert!(max / min < 1.5, "{} {}", min, max);
+
+        let points = fibonacci_lattice(1_000);
+        assert_eq!(points.len(), 1_000);
+        let (min, max) = min_max_spacing(&points);
+        assert!(max / min < 1.5, "{} {}", min, max);
+    }
+
+    #[test]
+    fn coastal_placement() {
+        // a winding coastline, which splits the leaves around it
+        let coastline: Vec<_> = (0..=200)
+            .map(|i| {
+                let angle = (i % 200) as f64 / 200.0 * 2.0 * PI;
+                let radius = 2.0 + 0.5 * (12.0 * angle).sin();
+                Point::from_coordinate(radius * angle.sin(), 20.0 + radius * angle.cos())
+            })
+            .collect();
+        let mut planet_grid = PolygonSpatialPartition::with_seed(4, 0);
+        planet_grid.add_polygons(&vec![Polygon::new(coastline)]);
+
+        let mut config = GeneratorConfig {
+            generator: Generator::Grid,
+            num_nodes: 5_000,
+            placement: Placement::Coastal,
+            coast_factor: 50.0,
+            neighbors: NeighborSelection::Sectors,
+            num_sectors: 4,
+            k: 1,
+            radius: 30_000.0,
+            visibility_radius: None,
+            simplify_tolerance: 1_000.0,
+            seed: 7,
+        };
+        let points = generate_points(&config, &planet_grid);
+        assert_eq!(points.len(), 5_000);
+        assert!(points == generate_points(&config, &planet_grid));
+        assert!(points.iter().all(|point| !planet_grid.is_on_polygon(point)));
+
+        let near = |points: &[Point]| {
+            let center = Point::from_coordinate(0.0, 20.0);
+            points
+                .iter()
+                .filter(|point| Arc::new(point, &center).central_angle() < 5f64.to_radians())
+                .count()
+        };
+        config.placement = Placement::Random;
+        let random = generate_points(&config, &planet_grid);
+        assert!(
+            near(&points) > 2 * near(&random),
+            "{} {}",
+            near(&points),
+            near(&random)
+        );
+    }
+
+    #[test]
+    fn neighbor_selection() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let points: Vec<_> = (0..300)
+            .map(|_| Point::from_coordinate(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)))
+            .collect();
+        let point_grid = generate_point_grid(&points);
+        let planet_grid = PolygonSpatialPartition::with_seed(10, 0);
+        let mut config = GeneratorConfig {
+            generator: Generator::Grid,
+            num_nodes: points.len() as u32,
+            placement: Placement::Random,
+            coast_factor: 1.0,
+            neighbors: NeighborSelection::Nearest,
+            num_sectors: 6,
+            k: 3,
+            radius: 20_000.0,
+            visibility_radius: None,
+            simplify_tolerance: 1_000.0,
+            seed: 0,
+        };
+
+        // the other points within the radius by their sector, nearest first
+        let sectors = |point: &Point, num_sectors: u32| {
+            let mut sectors = vec![Vec::new(); num_sectors as usize];
+            let mut others: Vec<_> = points
+                .iter()
+                .filter(|other| *other != point)
+                .map(|other| Arc::new(point, other))
+                .filter(|arc| radians_to_meter(arc.central_angle()) <= config.radius)
+                .collect();
+            others.sort_by(|x, y| x.central_angle().total_cmp(&y.central_angle()));
+            for arc in others {
+                let sector = arc.initial_bearing() / (2.0 * PI / num_sectors as f64);
+                sectors[(sector as usize).min(num_sectors as usize - 1)].push(*arc.to());
+            }
+            sectors
+        };
+        let targets = |arcs: &[Arc], point: &Point| -> HashSet<Point> {
+            arcs.iter()
+                .filter(|arc| arc.from() == point)
+                .map(|arc| *arc.to())
+                .collect()
+        };
+
+        let arcs = generate_arcs(&points, &point_grid, &planet_grid, &config);
+        for point in &points {
+            let mut nearest: Vec<_> = sectors(point, 1).remove(0);
+            nearest.truncate(3);
+            assert!(targets(&arcs, point) == nearest.into_iter().collect());
+        }
+
+        config.neighbors = NeighborSelection::Sectors;
+        config.k = 2;
+        let arcs = generate_arcs(&points, &point_grid, &planet_grid, &config);
+        for point in &points {
+            let expected: HashSet<_> = sectors(point, 6)
+                .into_iter()
+                .flat_map(|sector| sector.into_iter().take(2))
+                .collect();
+            assert!(targets(&arcs, point) == expected);
+        }
+    }
+
+    #[test]
+    fn visibility_graph() {
+        // a square island with three vertices on every side
+        let outline: Vec<_> = [
+            (0.0, 0.0),
+            (0.0, 0.5),
+            (0.0, 1.0),
+            (0.5, 1.0),
+            (1.0, 1.0),
+            (1.0, 0.5),
+            (1.0, 0.0),
+            (0.5, 0.0),
+            (0.0, 0.0),
+        ]
+        .iter()
+        .map(|&(latitude, longitude)| Point::from_coordinate(latitude, longitude))
+        .collect();
+        let mut planet = Planet::new();
+        planet.polygons.push(Polygon::new(outline));
+        let mut planet_grid = PolygonSpatialPartition::with_seed(10, 0);
+        planet_grid.add_polygons(&planet.polygons);
+        let mut config = GeneratorConfig {
+            generator: Generator::Visibility,
+            num_nodes: 0,
+            placement: Placement::Random,
+            coast_factor: 1.0,
+            neighbors: NeighborSelection::Sectors,
+            num_sectors: 4,
+            k: 1,
+            radius: 30_000.0,
+            visibility_radius: Some(500_000.0),
+            simplify_tolerance: 1_000.0,
+            seed: 0,
+        };
+
+        // only the corners are left, each next to the island
+        let points = generate_visibility_points(&planet, &planet_grid, &config);
+        assert_eq!(points.len(), 4);
+        for point in &points {
+            assert!(!planet_grid.is_on_polygon(point));
+            let corner = planet.polygons[0]
+                .outline
+                .iter()
+                .map(|corner| radians_to_meter(Arc::new(point, corner).central_angle()))
+                .fold(f64::INFINITY, f64::min);
+            assert!((corner - 1_000.0).abs() < 1.0, "{}", corner);
+        }
+
+        // the sides of the square, the diagonals cross the island
+        let point_grid = generate_point_grid(&points);
+        let arcs =
+            generate_visibility_arcs(&points, &point_grid, &planet_grid, config.max_arc_length());
+        assert_eq!(arcs.len(), 4);
+        assert!(arcs
+            .iter()
+            .all(|arc| radians_to_meter(arc.central_angle()) < 120_000.0));
+
+        // unbounded, the diagonals still cross the island
+        config.visibility_radius = None;
+        let arcs =
+            generate_visibility_arcs(&points, &point_grid, &planet_grid, config.max_arc_length());
+        assert_eq!(arcs.len(), 4);
+        config.visibility_radius = Some(50_000.0);
+        let arcs =
+            generate_visibility_arcs(&points, &point_grid, &planet_grid, config.max_arc_length());
+        assert!(arcs.is_empty());
+    }
 }
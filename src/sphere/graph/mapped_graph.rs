@@ -0,0 +1,433 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{BufWriter, Write},
+    ops::Range,
+};
+
+use memmap2::Mmap;
+
+use crate::sphere::{
+    geometry::{arc::Arc, point::Point},
+    graph::{binary_graph::fnv1a, error::GraphError, graph::Fmi},
+    routing::{
+        adjacency_array::{AdjacencyArray, GraphId},
+        graph_view::{Coordinates, GraphView},
+    },
+};
+
+/// First bytes of every memory mapped graph file.
+pub const MAGIC: [u8; 8] = *b"FMIMMAP\0";
+
+/// Version of the file layout, increased with every incompatible change.
+pub const VERSION: u32 = 1;
+
+const HEADER_LEN: usize = 24;
+
+/// Coordinates are stored as fixed point numbers in units of 1e-7 degrees, about a centimeter.
+const COORDINATE_SCALE: f64 = 1e7;
+
+/// A graph in a flat file layout that is memory mapped and queried in place, so opening it only
+/// takes one pass over the offsets and heads to validate them. Searches run on it through
+/// `GraphView` and `Coordinates` without copying it. All numbers are little endian:
+///
+/// | section     | content                                                 |
+/// |-------------|---------------------------------------------------------|
+/// | header      | magic, version, num_nodes, num_edges, reserved (u32)    |
+/// | offsets     | (num_nodes + 1) u32, as in `AdjacencyArray`             |
+/// | heads       | num_edges u32                                           |
+/// | weights     | num_edges u32, in meters                                |
+/// | coordinates | num_nodes (latitude, longitude) i32 in 1e-7 degrees     |
+///
+/// The header is 24 bytes long and every entry 4 bytes, so every section is 4 byte aligned.
+pub struct MappedGraph {
+    mmap: Mmap,
+    num_nodes: u32,
+    num_edges: u32,
+}
+
+/// The reason a file cannot be read as a memory mapped graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MappedGraphError {
+    /// The file does not start with the magic bytes.
+    NotAMappedGraph,
+    /// The file was written with another version of the layout.
+    UnsupportedVersion(u32),
+    /// The length of the file does not match the number of nodes and edges of the header.
+    Truncated,
+    /// The offsets decrease or do not add up to the number of edges, or a head is not a node.
+    Inconsistent,
+}
+
+impl fmt::Display for MappedGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MappedGraphError::NotAMappedGraph => write!(f, "not a memory mapped graph file"),
+            MappedGraphError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported version {}, expected version {}",
+                version, VERSION
+            ),
+            MappedGraphError::Truncated => write!(f, "file is truncated"),
+            MappedGraphError::Inconsistent => {
+                write!(f, "offsets or heads do not match the nodes and edges")
+            }
+        }
+    }
+}
+
+impl Error for MappedGraphError {}
+
+impl MappedGraph {
+    pub fn open(path: &str) -> Result<MappedGraph, GraphError> {
+        let invalid = |source| GraphError::Mapped {
+            path: path.to_string(),
+            source,
+        };
+        let file = File::open(path).map_err(GraphError::io(path))?;
+        // the file must not be modified while it is mapped, which holds for the files written once
+        // by `MappedGraph::to_file`
+        let mmap = unsafe { Mmap::map(&file) }.map_err(GraphError::io(path))?;
+
+        if mmap.len() < HEADER_LEN || mmap[..MAGIC.len()] != MAGIC {
+            return Err(invalid(MappedGraphError::NotAMappedGraph));
+        }
+        let version = read_u32(&mmap, 8);
+        if version != VERSION {
+            return Err(invalid(MappedGraphError::UnsupportedVersion(version)));
+        }
+
+        let graph = MappedGraph {
+            num_nodes: read_u32(&mmap, 12),
+            num_edges: read_u32(&mmap, 16),
+            mmap,
+        };
+        if graph.mmap.len() != graph.coordinates_start() + 8 * graph.num_nodes as usize {
+            return Err(invalid(MappedGraphError::Truncated));
+        }
+        // the searches index by offsets and heads without checking them
+        let mut offsets = (0..=graph.num_nodes).map(|node| graph.offset(node));
+        let offsets_valid = offsets.next() == Some(0)
+            && offsets
+                .try_fold(0, |previous, offset| {
+                    (previous <= offset && offset <= graph.num_edges).then_some(offset)
+                })
+                .is_some_and(|last| last == graph.num_edges);
+        let heads_valid = (0..graph.num_edges).all(|edge| {
+            read_u32(&graph.mmap, graph.heads_start() + 4 * edge as usize) < graph.num_nodes
+        });
+        if !offsets_valid || !heads_valid {
+            return Err(invalid(MappedGraphError::Inconsistent));
+        }
+
+        Ok(graph)
+    }
+
+    /// Writes the graph, node ids are the indices of points.
+    pub fn to_file(
+        path: &str,
+        points: &[Point],
+        graph: &AdjacencyArray,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&MAGIC)?;
+        for value in [VERSION, graph.num_nodes(), graph.num_edges(), 0] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        let mut offset: u32 = 0;
+        writer.write_all(&offset.to_le_bytes())?;
+        for node in 0..graph.num_nodes() {
+            offset += graph.edges(node).count() as u32;
+            writer.write_all(&offset.to_le_bytes())?;
+        }
+        for node in 0..graph.num_nodes() {
+            for (head, _) in graph.edges(node) {
+                writer.write_all(&head.to_le_bytes())?;
+            }
+        }
+        for node in 0..graph.num_nodes() {
+            for (_, weight) in graph.edges(node) {
+                writer.write_all(&weight.to_le_bytes())?;
+            }
+        }
+        for point in points {
+            for degrees in [point.latitude(), point.longitude()] {
+                let fixed = (degrees * COORDINATE_SCALE).round() as i32;
+                writer.write_all(&fixed.to_le_bytes())?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn num_nodes(&self) -> u32 {
+        self.num_nodes
+    }
+
+    pub fn num_edges(&self) -> u32 {
+        self.num_edges
+    }
+
+    /// Returns the same identity as `AdjacencyArray::graph_id` of the copied edges, hashed in
+    /// place as the offsets, heads and weights are stored in the same order.
+    pub fn graph_id(&self) -> GraphId {
+        GraphId {
+            num_nodes: self.num_nodes,
+            checksum: fnv1a(&self.mmap[HEADER_LEN..self.coordinates_start()]),
+        }
+    }
+
+    /// Returns the outgoing edges of node as (head, weight) pairs.
+    pub fn edges(&self, node: u32) -> MappedEdges<'_> {
+        MappedEdges {
+            graph: self,
+            edges: self.offset(node)..self.offset(node + 1),
+        }
+    }
+
+    /// Returns the (latitude, longitude) of node in degrees.
+    pub fn coordinate(&self, node: u32) -> (f64, f64) {
+        let at = self.coordinates_start() + 8 * node as usize;
+        let latitude = read_u32(&self.mmap, at) as i32;
+        let longitude = read_u32(&self.mmap, at + 4) as i32;
+        (
+            latitude as f64 / COORDINATE_SCALE,
+            longitude as f64 / COORDINATE_SCALE,
+        )
+    }
+
+    pub fn point(&self, node: u32) -> Point {
+        let (latitude, longitude) = self.coordinate(node);
+        Point::from_coordinate(latitude, longitude)
+    }
+
+    /// Copies the edges into an adjacency array, which is needed to compute speedup data.
+    pub fn to_adjacency_array(&self) -> AdjacencyArray {
+        let section = |start: usize, len: usize| -> Vec<u32> {
+            self.mmap[start..start + 4 * len]
+                .chunks_exact(4)
+                .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+                .collect()
+        };
+        AdjacencyArray::from_csr(
+            section(HEADER_LEN, self.num_nodes as usize + 1),
+            section(self.heads_start(), self.num_edges as usize),
+            section(self.weights_start(), self.num_edges as usize),
+        )
+    }
+
+    pub fn to_fmi(&self) -> Fmi {
+        let points: Vec<_> = (0..self.num_nodes).map(|node| self.point(node)).collect();
+        let arcs = (0..self.num_nodes)
+            .flat_map(|tail| {
+                let points = &points;
+                self.edges(tail)
+                    .map(move |(head, _)| Arc::new(&points[tail as usize], &points[head as usize]))
+            })
+            .collect();
+
+        Fmi { points, arcs }
+    }
+
+    fn offset(&self, node: u32) -> u32 {
+        read_u32(&self.mmap, HEADER_LEN + 4 * node as usize)
+    }
+
+    fn heads_start(&self) -> usize {
+        HEADER_LEN + 4 * (self.num_nodes as usize + 1)
+    }
+
+    fn weights_start(&self) -> usize {
+        self.heads_start() + 4 * self.num_edges as usize
+    }
+
+    fn coordinates_start(&self) -> usize {
+        self.weights_start() + 4 * self.num_edges as usize
+    }
+}
+
+/// The outgoing edges of a node of a `MappedGraph` as (head, weight) pairs, read in place.
+pub struct MappedEdges<'a> {
+    graph: &'a MappedGraph,
+    edges: Range<u32>,
+}
+
+impl Iterator for MappedEdges<'_> {
+    type Item = (u32, u32);
+
+    fn next(&mut self) -> Option<(u32, u32)> {
+        let edge = 4 * self.edges.next()? as usize;
+        Some((
+            read_u32(&self.graph.mmap, self.graph.heads_start() + edge),
+            read_u32(&self.graph.mmap, self.graph.weights_start() + edge),
+        ))
+    }
+}
+
+impl GraphView for MappedGraph {
+    type Edges<'a> = MappedEdges<'a>;
+
+    fn num_nodes(&self) -> u32 {
+        self.num_nodes
+    }
+
+    fn edges(&self, node: u32) -> MappedEdges<'_> {
+        self.edges(node)
+    }
+}
+
+impl Coordinates for MappedGraph {
+    fn num_points(&self) -> u32 {
+        self.num_nodes
+    }
+
+    fn point(&self, node: u32) -> Point {
+        self.point(node)
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::sphere::{
+        geometry::point::Point,
+        graph::{
+            error::GraphError,
+            graph::validate_edge_lengths,
+            mapped_graph::{MappedGraph, MappedGraphError, HEADER_LEN},
+        },
+        routing::{
+            a_star::AStar, adjacency_array::AdjacencyArray, dijkstra::Dijkstra,
+            heuristic::GreatCircle, route::PathFinding,
+        },
+        spatial_partition::kd_tree::KdTree,
+    };
+
+    #[test]
+    fn round_trip() {
+        let points = vec![
+            Point::from_coordinate(53.5, 9.9),
+            Point::from_coordinate(-33.9, 18.4),
+            Point::from_coordinate(1.3, 103.8),
+        ];
+        let graph =
+            AdjacencyArray::from_edges(3, &[(0, 1, 10), (1, 0, 10), (1, 2, 20), (2, 1, 21)]);
+
+        let path = std::env::temp_dir().join("mapped_graph_round_trip.mmap");
+        let path = path.to_str().unwrap();
+        MappedGraph::to_file(path, &points, &graph).unwrap();
+
+        let mapped = MappedGraph::open(path).unwrap();
+        assert_eq!(mapped.num_nodes(), 3);
+        assert_eq!(mapped.num_edges(), 4);
+        for node in 0..3 {
+            assert_eq!(
+                mapped.edges(node).collect::<Vec<_>>(),
+                graph.edges(node).collect::<Vec<_>>()
+            );
+            assert!(mapped
+                .point(node)
+                .is_approximately_equal(&points[node as usize]));
+        }
+        assert_eq!(mapped.coordinate(1), (-33.9, 18.4));
+        let copied = mapped.to_adjacency_array();
+        assert_eq!(copied.edges(2).collect::<Vec<_>>(), vec![(1, 21)]);
+        assert_eq!(mapped.to_fmi().arcs.len(), 4);
+        assert_eq!(mapped.graph_id(), graph.graph_id());
+
+        let bytes = fs::read(path).unwrap();
+        fs::write(path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            MappedGraph::open(path),
+            Err(GraphError::Mapped {
+                source: MappedGraphError::Truncated,
+                ..
+            })
+        ));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn search_in_place() {
+        let points: Vec<_> = (0..4)
+            .map(|i| Point::from_coordinate(0.0, i as f64 * 0.1))
+            .collect();
+        let mut edges = Vec::new();
+        for (tail, head, weight) in [(0, 1, 11200), (1, 2, 11200), (2, 3, 11200), (0, 3, 40000)] {
+            edges.push((tail, head, weight));
+            edges.push((head, tail, weight));
+        }
+        let graph = AdjacencyArray::from_edges(4, &edges);
+
+        let path = std::env::temp_dir().join("mapped_graph_search_in_place.mmap");
+        let path = path.to_str().unwrap();
+        MappedGraph::to_file(path, &points, &graph).unwrap();
+        let mapped = MappedGraph::open(path).unwrap();
+
+        let expected = Dijkstra::new(&graph).shortest_path(0, 3).unwrap();
+        assert_eq!(expected.path, vec![0, 1, 2, 3]);
+        assert_eq!(
+            Dijkstra::new(&mapped).shortest_path(0, 3),
+            Some(expected.clone())
+        );
+        let a_star = AStar::new(&mapped, GreatCircle::new(&mapped));
+        assert_eq!(
+            a_star.shortest_path(0, 3).map(|route| route.path),
+            Some(expected.path)
+        );
+        let kd_tree = KdTree::from_coordinates(&mapped);
+        assert_eq!(
+            kd_tree.nearest(&Point::from_coordinate(0.01, 0.19)),
+            Some(2)
+        );
+
+        // the arc from 0 to 3 is about 33 km long and the second edge of node 0
+        assert!(validate_edge_lengths(&mapped, 35_000.0).is_ok());
+        assert!(matches!(
+            validate_edge_lengths(&mapped, 20_000.0),
+            Err(GraphError::ArcTooLong { arc: 1, .. })
+        ));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn corrupted_offsets_and_heads() {
+        let points = vec![Point::from_coordinate(0.0, 0.0); 3];
+        // offsets are [0, 1, 3, 4]
+        let graph = AdjacencyArray::from_edges(3, &[(0, 1, 1), (1, 0, 1), (1, 2, 1), (2, 1, 1)]);
+
+        let path = std::env::temp_dir().join("mapped_graph_corrupted.mmap");
+        let path = path.to_str().unwrap();
+        MappedGraph::to_file(path, &points, &graph).unwrap();
+        let bytes = fs::read(path).unwrap();
+        assert!(MappedGraph::open(path).is_ok());
+
+        let heads_start = HEADER_LEN + 4 * 4;
+        // a middle offset beyond the next one, the last offset still matches the edges
+        for (at, value) in [
+            (HEADER_LEN + 4, 4),
+            (HEADER_LEN + 8, 5),
+            (heads_start + 4, 3),
+        ] {
+            let mut corrupted = bytes.clone();
+            corrupted[at..at + 4].copy_from_slice(&u32::to_le_bytes(value));
+            fs::write(path, &corrupted).unwrap();
+            assert!(matches!(
+                MappedGraph::open(path),
+                Err(GraphError::Mapped {
+                    source: MappedGraphError::Inconsistent,
+                    ..
+                })
+            ));
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
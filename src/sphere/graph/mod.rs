@@ -1,2 +1,6 @@
+pub mod binary_graph;
+pub mod error;
 pub mod graph;
 pub mod graph_generator;
+pub mod mapped_graph;
+pub mod text_graph;
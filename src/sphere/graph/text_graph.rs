@@ -0,0 +1,361 @@
+use std::{
+    cell::Cell,
+    error::Error,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+};
+
+use crate::sphere::{
+    geometry::{arc::Arc, point::Point},
+    graph::{
+        error::{parse_field, parse_point, GraphError},
+        graph::Fmi,
+    },
+    routing::adjacency_array::AdjacencyArray,
+};
+
+/// Number of nodes or edges space is reserved for up front. The counts of the header are not
+/// trusted beyond it, as a corrupt header would otherwise allocate arbitrary amounts of memory.
+const MAX_PREALLOCATION: usize = 1 << 20;
+
+/// A node of the FMI text format, `nodeID nodeID2 latitude longitude elevation`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextNode {
+    /// The index of the node, the nodes are numbered consecutively starting at zero.
+    pub id: u32,
+    /// A second id, e.g. of the OpenStreetMap node.
+    pub id2: u64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: i32,
+}
+
+/// An edge of the FMI text format, `srcIDX trgIDX cost type maxspeed`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdge {
+    pub source: u32,
+    pub target: u32,
+    /// The length of the edge in meters.
+    pub cost: u32,
+    pub edge_type: u32,
+    pub max_speed: u32,
+}
+
+/// A graph in the FMI text format used by the course tools. The file starts with comment lines
+/// prefixed by `#` and an empty line, followed by the number of nodes, the number of edges, one
+/// line per node and one line per edge.
+///
+/// Graphs written from an `Fmi` have symmetric edges, a second id equal to the id, and zero
+/// elevation, edge type and max speed, as none of them is known for the sea.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextGraph {
+    pub nodes: Vec<TextNode>,
+    pub edges: Vec<TextEdge>,
+}
+
+impl TextGraph {
+    pub fn from_fmi(fmi: &Fmi) -> TextGraph {
+        let edges = AdjacencyArray::from_edges(fmi.points.len() as u32, &fmi.symmetric_edges());
+        TextGraph::from_graph(&fmi.points, &edges)
+    }
+
+    /// Creates a text graph from edges whose weights are written as costs, node ids are the
+    /// indices of points.
+    pub fn from_graph(points: &[Point], graph: &AdjacencyArray) -> TextGraph {
+        let nodes = points
+            .iter()
+            .enumerate()
+            .map(|(id, point)| TextNode {
+                id: id as u32,
+                id2: id as u64,
+                latitude: point.latitude(),
+                longitude: point.longitude(),
+                elevation: 0,
+            })
+            .collect();
+        let edges = (0..graph.num_nodes())
+            .flat_map(|source| {
+                graph.edges(source).map(move |(target, cost)| TextEdge {
+                    source,
+                    target,
+                    cost,
+                    edge_type: 0,
+                    max_speed: 0,
+                })
+            })
+            .collect();
+
+        TextGraph { nodes, edges }
+    }
+
+    pub fn from_file(path: &str) -> Result<TextGraph, GraphError> {
+        let reader = BufReader::new(File::open(path).map_err(GraphError::io(path))?);
+        let mut lines = reader
+            .lines()
+            .enumerate()
+            .map(|(index, line)| line.map(|line| (index + 1, line)))
+            .filter(|line| match line {
+                Ok((_, line)) => !line.trim().is_empty() && !line.starts_with('#'),
+                Err(_) => true,
+            });
+        let last_number = Cell::new(0);
+        let mut next_line = || -> Result<Option<(usize, String)>, GraphError> {
+            match lines.next() {
+                Some(Ok((number, line))) => {
+                    last_number.set(number);
+                    Ok(Some((number, line)))
+                }
+                Some(Err(error)) => Err(GraphError::io(path)(error)),
+                None => Ok(None),
+            }
+        };
+        let end_of_file = |message: String| GraphError::parse(path, last_number.get() + 1, message);
+
+        let Some((number, line)) = next_line()? else {
+            return Err(end_of_file("unexpected end of file".to_string()));
+        };
+        let num_nodes: usize = parse_field(Some(line.trim()), path, number)?;
+        let Some((number, line)) = next_line()? else {
+            return Err(end_of_file("unexpected end of file".to_string()));
+        };
+        let num_edges: usize = parse_field(Some(line.trim()), path, number)?;
+
+        let mut nodes = Vec::with_capacity(num_nodes.min(MAX_PREALLOCATION));
+        for id in 0..num_nodes {
+            let Some((number, line)) = next_line()? else {
+                return Err(end_of_file(format!(
+                    "expected {} nodes, found {}",
+                    num_nodes, id
+                )));
+            };
+            let fields = fields(&line, path, number)?;
+            let node = TextNode {
+                id: parse_field(Some(fields[0]), path, number)?,
+                id2: parse_field(Some(fields[1]), path, number)?,
+                latitude: parse_field(Some(fields[2]), path, number)?,
+                longitude: parse_field(Some(fields[3]), path, number)?,
+                elevation: parse_field(Some(fields[4]), path, number)?,
+            };
+            if node.id as usize != id {
+                return Err(GraphError::parse(
+                    path,
+                    number,
+                    format!("expected node {}", id),
+                ));
+            }
+            // checks the range of the coordinate
+            parse_point(node.latitude, node.longitude, path, number)?;
+            nodes.push(node);
+        }
+
+        let mut edges = Vec::with_capacity(num_edges.min(MAX_PREALLOCATION));
+        for index in 0..num_edges {
+            let Some((number, line)) = next_line()? else {
+                return Err(end_of_file(format!(
+                    "expected {} edges, found {}",
+                    num_edges, index
+                )));
+            };
+            let fields = fields(&line, path, number)?;
+            let edge = TextEdge {
+                source: parse_field(Some(fields[0]), path, number)?,
+                target: parse_field(Some(fields[1]), path, number)?,
+                cost: parse_field(Some(fields[2]), path, number)?,
+                edge_type: parse_field(Some(fields[3]), path, number)?,
+                max_speed: parse_field(Some(fields[4]), path, number)?,
+            };
+            for node in [edge.source, edge.target] {
+                if node as usize >= num_nodes {
+                    return Err(GraphError::parse(
+                        path,
+                        number,
+                        format!("unknown node {}", node),
+                    ));
+                }
+            }
+            edges.push(edge);
+        }
+
+        if let Some((number, _)) = next_line()? {
+            return Err(GraphError::parse(
+                path,
+                number,
+                format!("expected {} edges, found more", num_edges),
+            ));
+        }
+
+        Ok(TextGraph { nodes, edges })
+    }
+
+    pub fn to_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "# written by osm_converter")?;
+        writeln!(writer)?;
+        writeln!(writer, "{}", self.nodes.len())?;
+        writeln!(writer, "{}", self.edges.len())?;
+        for node in &self.nodes {
+            writeln!(
+                writer,
+                "{} {} {} {} {}",
+                node.id, node.id2, node.latitude, node.longitude, node.elevation
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                writer,
+                "{} {} {} {} {}",
+                edge.source, edge.target, edge.cost, edge.edge_type, edge.max_speed
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the edges with their costs as weights, node ids are the ids of the file. Edge type
+    /// and max speed are not used for routing on the sea.
+    pub fn adjacency_array(&self) -> AdjacencyArray {
+        let edges: Vec<_> = self
+            .edges
+            .iter()
+            .map(|edge| (edge.source, edge.target, edge.cost))
+            .collect();
+        AdjacencyArray::from_edges(self.nodes.len() as u32, &edges)
+    }
+
+    /// Returns the geometry of the graph, node ids are the indices of the points. The costs are
+    /// not part of it, see `adjacency_array`.
+    pub fn to_fmi(&self) -> Fmi {
+        let points: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|node| Point::from_coordinate(node.latitude, node.longitude))
+            .collect();
+        let arcs = self
+            .edges
+            .iter()
+            .map(|edge| Arc::new(&points[edge.source as usize], &points[edge.target as usize]))
+            .collect();
+
+        Fmi { points, arcs }
+    }
+}
+
+impl Fmi {
+    pub fn from_fmi_file(path: &str) -> Result<Fmi, GraphError> {
+        Ok(TextGraph::from_file(path)?.to_fmi())
+    }
+
+    pub fn to_fmi_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        TextGraph::from_fmi(self).to_file(path)
+    }
+}
+
+/// Splits a node or edge line, which both have five fields.
+fn fields<'a>(line: &'a str, path: &str, number: usize) -> Result<Vec<&'a str>, GraphError> {
+    let fields: Vec<_> = line.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(GraphError::parse(
+            path,
+            number,
+            format!("expected 5 fields, found {}", fields.len()),
+        ));
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::sphere::graph::{
+        error::GraphError,
+        graph::read_graph,
+        text_graph::{TextEdge, TextGraph},
+    };
+
+    #[test]
+    fn read_and_write() {
+        let path = std::env::temp_dir().join("text_graph_read_and_write.fmi");
+        let path = path.to_str().unwrap();
+        fs::write(
+            path,
+            "# Id : 42\n\
+             # Timestamp : 0\n\
+             \n\
+             3\n\
+             2\n\
+             0 163358 48.6 8.9 0\n\
+             1 163359 48.7 8.9 412\n\
+             2 163360 48.7 9.0 380\n\
+             0 1 11119 2 50\n\
+             1 2 7342 2 50\n",
+        )
+        .unwrap();
+
+        let graph = TextGraph::from_file(path).unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.nodes[1].id2, 163359);
+        assert_eq!(graph.nodes[1].elevation, 412);
+        assert_eq!(
+            graph.edges[1],
+            TextEdge {
+                source: 1,
+                target: 2,
+                cost: 7342,
+                edge_type: 2,
+                max_speed: 50,
+            }
+        );
+        let edges = graph.adjacency_array();
+        assert_eq!(edges.edges(1).collect::<Vec<_>>(), vec![(2, 7342)]);
+
+        graph.to_file(path).unwrap();
+        assert_eq!(TextGraph::from_file(path).unwrap(), graph);
+
+        // the symmetric graph of the fmi
+        let fmi = graph.to_fmi();
+        let symmetric = TextGraph::from_fmi(&fmi);
+        assert_eq!(symmetric.edges.len(), 4);
+        assert!(fmi.points[2].is_approximately_equal(&symmetric.to_fmi().points[2]));
+
+        // the costs are kept and nodes at the same coordinate keep their ids
+        fs::write(
+            path,
+            "2\n2\n0 0 48.6 8.9 0\n1 1 48.6 8.9 0\n0 1 5 0 0\n1 0 7 0 0\n",
+        )
+        .unwrap();
+        let (fmi, edges) = read_graph(path).unwrap();
+        assert_eq!(fmi.points.len(), 2);
+        assert_eq!(edges.edges(0).collect::<Vec<_>>(), vec![(1, 5)]);
+        assert_eq!(edges.edges(1).collect::<Vec<_>>(), vec![(0, 7)]);
+
+        fs::write(path, "3\n2\n0 0 48.6 8.9 0\n1 1 48.7\n").unwrap();
+        let error = TextGraph::from_file(path).err().unwrap();
+        assert!(matches!(error, GraphError::Parse { line: 4, .. }));
+        assert!(error
+            .to_string()
+            .ends_with(":4: expected 5 fields, found 3"));
+
+        fs::write(path, "2\n1\n0 0 48.6 8.9 0\n1 1 48.7 8.9 0\n0 2 10 0 0\n").unwrap();
+        let error = TextGraph::from_file(path).err().unwrap();
+        assert!(error.to_string().ends_with(":5: unknown node 2"));
+
+        fs::write(path, "2\n1\n0 0 48.6 8.9 0\n").unwrap();
+        let error = TextGraph::from_file(path).err().unwrap();
+        assert!(error.to_string().ends_with(":4: expected 2 nodes, found 1"));
+
+        fs::write(path, "1\n0\n0 0 48.6 8.9 0\n0 0 10 0 0\n").unwrap();
+        let error = TextGraph::from_file(path).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with(":4: expected 0 edges, found more"));
+
+        // the header is not trusted for the allocation
+        fs::write(path, "4000000000\n4000000000\n0 0 48.6 8.9 0\n").unwrap();
+        let error = TextGraph::from_file(path).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with(":4: expected 4000000000 nodes, found 1"));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,3 +1,4 @@
 pub mod geometry;
 pub mod graph;
+pub mod routing;
 pub mod spatial_partition;
@@ -0,0 +1,137 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use super::{
+    adjacency_array::AdjacencyArray,
+    graph_view::GraphView,
+    heuristic::Heuristic,
+    route::{offset_of, unwind_from_start, PathFinding, Route},
+};
+
+/// A* search on any graph, e.g. an `AdjacencyArray` or a memory mapped graph.
+pub struct AStar<'a, H: Heuristic, G: ?Sized = AdjacencyArray> {
+    graph: &'a G,
+    heuristic: H,
+}
+
+impl<'a, H: Heuristic, G: GraphView + ?Sized> AStar<'a, H, G> {
+    pub fn new(graph: &'a G, heuristic: H) -> AStar<'a, H, G> {
+        AStar { graph, heuristic }
+    }
+}
+
+impl<H: Heuristic, G: GraphView + ?Sized> PathFinding for AStar<'_, H, G> {
+    fn shortest_path(&self, source: u32, target: u32) -> Option<Route> {
+        self.shortest_path_between(&[(source, 0)], &[(target, 0)])
+    }
+
+    /// Searches from all sources at once, guided by the lower bound to the nearest target plus
+    /// its offset. As the minimum of consistent bounds, it is consistent as well.
+    fn shortest_path_between(
+        &self,
+        sources: &[(u32, u32)],
+        targets: &[(u32, u32)],
+    ) -> Option<Route> {
+        let num_nodes = self.graph.num_nodes() as usize;
+        let mut distances = vec![u32::MAX; num_nodes];
+        let mut predecessors = vec![u32::MAX; num_nodes];
+        let mut queue = BinaryHeap::new();
+        let mut settled = 0;
+
+        let lower_bound = |node: u32| {
+            targets
+                .iter()
+                .map(|&(target, offset)| {
+                    self.heuristic
+                        .lower_bound(node, target)
+                        .saturating_add(offset)
+                })
+                .min()
+                .unwrap_or(u32::MAX)
+        };
+        for &(source, offset) in sources {
+            if offset < distances[source as usize] {
+                distances[source as usize] = offset;
+                queue.push(Reverse((
+                    offset.saturating_add(lower_bound(source)),
+                    offset,
+                    source,
+                )));
+            }
+        }
+
+        // (distance including the offset, target) of the best target settled so far
+        let mut best = (u32::MAX, u32::MAX);
+        while let Some(Reverse((key, distance, node))) = queue.pop() {
+            if key >= best.0 {
+                break;
+            }
+            if distance > distances[node as usize] {
+                continue;
+            }
+            settled += 1;
+            if let Some(offset) = offset_of(node, targets) {
+                best = best.min((distance.saturating_add(offset), node));
+            }
+
+            for (head, weight) in self.graph.edges(node) {
+                let alternative = distance + weight;
+                if alternative < distances[head as usize] {
+                    distances[head as usize] = alternative;
+                    predecessors[head as usize] = node;
+                    let key = alternative.saturating_add(lower_bound(head));
+                    queue.push(Reverse((key, alternative, head)));
+                }
+            }
+        }
+
+        let (distance, target) = best;
+        (distance != u32::MAX).then(|| Route {
+            path: unwind_from_start(&predecessors, target),
+            distance,
+            settled,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::{
+        geometry::{arc::Arc, point::Point},
+        graph::graph::Fmi,
+        routing::{
+            a_star::AStar, adjacency_array::AdjacencyArray, dijkstra::Dijkstra,
+            heuristic::GreatCircle, route::PathFinding,
+        },
+    };
+
+    #[test]
+    fn same_distance_as_dijkstra() {
+        // 5x5 grid with a spacing of 0.1 degree and a missing node in the middle
+        let points: Vec<_> = (0..25)
+            .filter(|&i| i != 12)
+            .map(|i| Point::from_coordinate((i / 5) as f64 * 0.1, (i % 5) as f64 * 0.1))
+            .collect();
+        let mut arcs = Vec::new();
+        for from in points.iter() {
+            for to in points.iter() {
+                let arc = Arc::new(from, to);
+                if from != to && arc.central_angle() < 0.1_f64.to_radians() * 1.01 {
+                    arcs.push(arc);
+                }
+            }
+        }
+        let fmi = Fmi { points, arcs };
+        let graph = AdjacencyArray::from_fmi(&fmi);
+
+        let dijkstra = Dijkstra::new(&graph);
+        let a_star = AStar::new(&graph, GreatCircle::new(fmi.points.as_slice()));
+        for source in 0..graph.num_nodes() {
+            for target in 0..graph.num_nodes() {
+                let expected = dijkstra.shortest_path(source, target).unwrap();
+                let route = a_star.shortest_path(source, target).unwrap();
+                assert_eq!(route.distance, expected.distance);
+                assert!(route.settled <= expected.settled);
+            }
+        }
+    }
+}
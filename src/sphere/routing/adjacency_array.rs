@@ -0,0 +1,167 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    iter::{Cloned, Zip},
+    slice,
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::sphere::{
+    geometry::{arc::Arc, point::radians_to_meter},
+    graph::{binary_graph::fnv1a, graph::Fmi},
+};
+
+use super::graph_view::GraphView;
+
+/// Compressed sparse row representation of a directed graph. The outgoing edges of node `v` are
+/// stored at the indices `offsets[v]..offsets[v + 1]` of `heads` and `weights`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AdjacencyArray {
+    offsets: Vec<u32>,
+    heads: Vec<u32>,
+    weights: Vec<u32>,
+}
+
+impl AdjacencyArray {
+    /// Creates an adjacency array from a list of (tail, head, weight) edges.
+    pub fn from_edges(num_nodes: u32, edges: &[(u32, u32, u32)]) -> AdjacencyArray {
+        let mut edges = edges.to_vec();
+        edges.sort_unstable();
+
+        let mut offsets = vec![0; num_nodes as usize + 1];
+        edges
+            .iter()
+            .for_each(|&(tail, _, _)| offsets[tail as usize + 1] += 1);
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+
+        AdjacencyArray {
+            offsets,
+            heads: edges.iter().map(|&(_, head, _)| head).collect(),
+            weights: edges.iter().map(|&(_, _, weight)| weight).collect(),
+        }
+    }
+
+    /// Creates an adjacency array from its compressed sparse rows, e.g. read from a file.
+    pub fn from_csr(offsets: Vec<u32>, heads: Vec<u32>, weights: Vec<u32>) -> AdjacencyArray {
+        assert_eq!(heads.len(), weights.len());
+        assert_eq!(offsets.last().map(|&last| last as usize), Some(heads.len()));
+        AdjacencyArray {
+            offsets,
+            heads,
+            weights,
+        }
+    }
+
+    /// Creates an adjacency array from the arcs of the graph. Node ids are the indices of
+    /// `fmi.points`, weights are the arc lengths in meters.
+    pub fn from_fmi(fmi: &Fmi) -> AdjacencyArray {
+        let ids: HashMap<_, _> = fmi
+            .points
+            .iter()
+            .enumerate()
+            .map(|(id, point)| (*point, id as u32))
+            .collect();
+
+        let edges: Vec<_> = fmi
+            .arcs
+            .iter()
+            .map(|arc| (ids[arc.from()], ids[arc.to()], arc_weight(arc)))
+            .collect();
+
+        AdjacencyArray::from_edges(fmi.points.len() as u32, &edges)
+    }
+
+    /// Checks that the offsets start at 0, do not decrease and end at the number of edges, and
+    /// that every head is a node, e.g. after the adjacency array was decoded from a file.
+    pub fn is_consistent(&self) -> bool {
+        let num_nodes = self.offsets.len().saturating_sub(1);
+        self.offsets.first() == Some(&0)
+            && self.offsets.windows(2).all(|pair| pair[0] <= pair[1])
+            && self.offsets.last().map(|&last| last as usize) == Some(self.heads.len())
+            && self.weights.len() == self.heads.len()
+            && self.heads.iter().all(|&head| (head as usize) < num_nodes)
+    }
+
+    pub fn num_nodes(&self) -> u32 {
+        (self.offsets.len() - 1) as u32
+    }
+
+    pub fn num_edges(&self) -> u32 {
+        self.heads.len() as u32
+    }
+
+    /// Returns the identity of the graph, which speedup data stores to detect that it is loaded
+    /// together with another graph than it was computed for.
+    pub fn graph_id(&self) -> GraphId {
+        let bytes: Vec<u8> = self
+            .offsets
+            .iter()
+            .chain(&self.heads)
+            .chain(&self.weights)
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        GraphId {
+            num_nodes: self.num_nodes(),
+            checksum: fnv1a(&bytes),
+        }
+    }
+
+    /// Returns the outgoing edges of node as (head, weight) pairs.
+    pub fn edges(&self, node: u32) -> Edges<'_> {
+        let start = self.offsets[node as usize] as usize;
+        let end = self.offsets[node as usize + 1] as usize;
+        self.heads[start..end]
+            .iter()
+            .cloned()
+            .zip(self.weights[start..end].iter().cloned())
+    }
+
+    /// Returns the weight of the lightest edge from tail to head.
+    pub fn weight(&self, tail: u32, head: u32) -> Option<u32> {
+        self.edges(tail)
+            .filter(|&(edge_head, _)| edge_head == head)
+            .map(|(_, weight)| weight)
+            .min()
+    }
+}
+
+/// The outgoing edges of a node of an `AdjacencyArray` as (head, weight) pairs.
+pub type Edges<'a> = Zip<Cloned<slice::Iter<'a, u32>>, Cloned<slice::Iter<'a, u32>>>;
+
+impl GraphView for AdjacencyArray {
+    type Edges<'a> = Edges<'a>;
+
+    fn num_nodes(&self) -> u32 {
+        self.num_nodes()
+    }
+
+    fn edges(&self, node: u32) -> Edges<'_> {
+        self.edges(node)
+    }
+}
+
+/// The number of nodes and a checksum of the edges of a graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphId {
+    pub num_nodes: u32,
+    pub checksum: u64,
+}
+
+impl fmt::Display for GraphId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes with checksum {:016x}",
+            self.num_nodes, self.checksum
+        )
+    }
+}
+
+/// Returns the length of the arc in meters. It is rounded up, so that the great circle distance
+/// between two nodes is never longer than any path between them.
+pub fn arc_weight(arc: &Arc) -> u32 {
+    radians_to_meter(arc.central_angle()).ceil() as u32
+}
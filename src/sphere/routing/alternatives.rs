@@ -0,0 +1,196 @@
+use ahash::{AHashMap, AHashSet};
+
+use super::{
+    adjacency_array::AdjacencyArray,
+    dijkstra::Dijkstra,
+    graph_view::GraphView,
+    route::{PathFinding, Route},
+};
+
+/// Maximal number of routes returned by `Alternatives::routes`, every route costs up to three
+/// searches of the whole graph.
+pub const MAX_ALTERNATIVES: usize = 10;
+
+/// Maximal factor the weight of an edge is multiplied with, however often it is penalized. Keeps
+/// penalized weights far from overflowing.
+const MAX_PENALTY: f64 = 10.0;
+
+/// Finds alternative routes with the penalty method. After every search, the weights of the
+/// edges of the route found are increased, so that the next search prefers other edges. A route
+/// is accepted if it is not much longer than the shortest route and does not share too much of
+/// its length with a route accepted before.
+pub struct Alternatives<'a, G: ?Sized = AdjacencyArray> {
+    graph: &'a G,
+    /// Factor the weights of the edges of a found route are multiplied with, every time the edge
+    /// is part of a found route, up to `MAX_PENALTY`.
+    pub penalty: f64,
+    /// Maximal ratio between the length of an alternative and the shortest route.
+    pub max_stretch: f64,
+    /// Maximal share of the length of an alternative that may overlap with another route.
+    pub max_overlap: f64,
+}
+
+impl<'a, G: GraphView + ?Sized> Alternatives<'a, G> {
+    pub fn new(graph: &'a G) -> Alternatives<'a, G> {
+        Alternatives {
+            graph,
+            penalty: 1.4,
+            max_stretch: 1.5,
+            max_overlap: 0.6,
+        }
+    }
+
+    /// Returns up to k routes from source to target, starting with the shortest one. k is
+    /// clamped to `MAX_ALTERNATIVES`. The graph needs to be symmetric, as both directions of an
+    /// edge are penalized.
+    pub fn routes(&self, source: u32, target: u32, k: usize) -> Vec<Route> {
+        let k = k.min(MAX_ALTERNATIVES);
+        let dijkstra = Dijkstra::new(self.graph);
+        let Some(shortest) = dijkstra.shortest_path(source, target) else {
+            return Vec::new();
+        };
+        let max_distance = (shortest.distance as f64 * self.max_stretch) as u32;
+
+        // number of times every undirected edge was penalized, the graph itself is not changed
+        let mut penalties = AHashMap::new();
+        penalize(&mut penalties, &shortest.path);
+        let mut edge_sets = vec![edge_set(&shortest.path)];
+        let mut routes = vec![shortest];
+
+        for _ in 0..3 * k {
+            if routes.len() >= k {
+                break;
+            }
+            let Some(mut candidate) =
+                dijkstra.shortest_path_with(source, target, |tail, head, weight| {
+                    self.penalized_weight(&penalties, tail, head, weight)
+                })
+            else {
+                break;
+            };
+            penalize(&mut penalties, &candidate.path);
+
+            candidate.distance = self.length(&candidate.path);
+            if candidate.distance > max_distance {
+                continue;
+            }
+
+            let edges = edge_set(&candidate.path);
+            let max_shared = edge_sets
+                .iter()
+                .map(|accepted| {
+                    candidate
+                        .path
+                        .windows(2)
+                        .filter(|edge| accepted.contains(&undirected(edge[0], edge[1])))
+                        .map(|edge| self.graph.weight(edge[0], edge[1]).unwrap())
+                        .sum::<u32>()
+                })
+                .max()
+                .unwrap_or(0);
+            if max_shared as f64 > candidate.distance as f64 * self.max_overlap {
+                continue;
+            }
+
+            edge_sets.push(edges);
+            routes.push(candidate);
+        }
+
+        routes
+    }
+
+    /// Returns the weight of the edge multiplied by the penalty for every time it was penalized.
+    fn penalized_weight(
+        &self,
+        penalties: &AHashMap<(u32, u32), i32>,
+        tail: u32,
+        head: u32,
+        weight: u32,
+    ) -> u32 {
+        match penalties.get(&undirected(tail, head)) {
+            Some(&count) => {
+                let factor = self.penalty.powi(count).min(MAX_PENALTY);
+                // casting saturates at u32::MAX
+                (weight as f64 * factor).ceil() as u32
+            }
+            None => weight,
+        }
+    }
+
+    /// Returns the length of the path with the original weights.
+    fn length(&self, path: &[u32]) -> u32 {
+        path.windows(2)
+            .map(|edge| self.graph.weight(edge[0], edge[1]).unwrap())
+            .fold(0, u32::saturating_add)
+    }
+}
+
+fn penalize(penalties: &mut AHashMap<(u32, u32), i32>, path: &[u32]) {
+    for edge in path.windows(2) {
+        *penalties.entry(undirected(edge[0], edge[1])).or_insert(0) += 1;
+    }
+}
+
+fn undirected(tail: u32, head: u32) -> (u32, u32) {
+    (tail.min(head), tail.max(head))
+}
+
+fn edge_set(path: &[u32]) -> AHashSet<(u32, u32)> {
+    path.windows(2)
+        .map(|edge| undirected(edge[0], edge[1]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::routing::{adjacency_array::AdjacencyArray, alternatives::Alternatives};
+
+    #[test]
+    fn disjoint_alternatives() {
+        // three disjoint routes from 0 to 3 with the lengths 20, 22 and 100
+        let mut edges = Vec::new();
+        let undirected_edges = [
+            (0, 1, 10),
+            (1, 3, 10),
+            (0, 2, 11),
+            (2, 3, 11),
+            (0, 4, 50),
+            (4, 3, 50),
+        ];
+        for (tail, head, weight) in undirected_edges {
+            edges.push((tail, head, weight));
+            edges.push((head, tail, weight));
+        }
+        let graph = AdjacencyArray::from_edges(5, &edges);
+        let alternatives = Alternatives::new(&graph);
+
+        let routes = alternatives.routes(0, 3, 3);
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].path, vec![0, 1, 3]);
+        assert_eq!(routes[0].distance, 20);
+        assert_eq!(routes[1].path, vec![0, 2, 3]);
+        assert_eq!(routes[1].distance, 22);
+
+        assert_eq!(alternatives.routes(0, 3, 1).len(), 1);
+    }
+
+    #[test]
+    fn weights_near_overflow() {
+        // the penalized weights of both routes would overflow without the cap
+        let heavy = u32::MAX / 8;
+        let mut edges = Vec::new();
+        for (tail, head, weight) in [(0, 1, heavy), (1, 3, 1), (0, 2, heavy), (2, 3, 2)] {
+            edges.push((tail, head, weight));
+            edges.push((head, tail, weight));
+        }
+        let graph = AdjacencyArray::from_edges(4, &edges);
+        let mut alternatives = Alternatives::new(&graph);
+        alternatives.penalty = 100.0;
+
+        let routes = alternatives.routes(0, 3, usize::MAX);
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].path, vec![0, 1, 3]);
+        assert_eq!(routes[1].path, vec![0, 2, 3]);
+        assert_eq!(routes[1].distance, heavy + 2);
+    }
+}
@@ -0,0 +1,182 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use super::{
+    adjacency_array::AdjacencyArray,
+    graph_view::GraphView,
+    route::{unwind_from_start, PathFinding, Route},
+};
+
+/// Runs a forward search from the source and a backward search from the target at the same time.
+///
+/// The backward search uses the same edges as the forward search, so the graph needs to be
+/// symmetric, which is the case for every graph written by `Fmi::to_gr_co_file`.
+pub struct BidirectionalDijkstra<'a, G: ?Sized = AdjacencyArray> {
+    graph: &'a G,
+}
+
+/// State of one search direction.
+struct Search {
+    distances: Vec<u32>,
+    predecessors: Vec<u32>,
+    queue: BinaryHeap<Reverse<(u32, u32)>>,
+}
+
+impl Search {
+    /// Starts a search from every (node, offset) pair at once.
+    fn new(num_nodes: usize, starts: &[(u32, u32)]) -> Search {
+        let mut search = Search {
+            distances: vec![u32::MAX; num_nodes],
+            predecessors: vec![u32::MAX; num_nodes],
+            queue: BinaryHeap::new(),
+        };
+        for &(start, offset) in starts {
+            if offset < search.distances[start as usize] {
+                search.distances[start as usize] = offset;
+                search.queue.push(Reverse((offset, start)));
+            }
+        }
+        search
+    }
+
+    /// Returns the smallest key in the queue, or `u32::MAX` if the queue is empty.
+    fn min_key(&self) -> u32 {
+        self.queue
+            .peek()
+            .map(|Reverse((distance, _))| *distance)
+            .unwrap_or(u32::MAX)
+    }
+}
+
+impl<'a, G: GraphView + ?Sized> BidirectionalDijkstra<'a, G> {
+    pub fn new(graph: &'a G) -> BidirectionalDijkstra<'a, G> {
+        BidirectionalDijkstra { graph }
+    }
+
+    /// Settles the next node of search and updates the best meeting node found so far.
+    fn step(&self, search: &mut Search, other: &Search, best: &mut (u32, u32)) -> bool {
+        let Some(Reverse((distance, node))) = search.queue.pop() else {
+            return false;
+        };
+        if distance > search.distances[node as usize] {
+            return false;
+        }
+
+        for (head, weight) in self.graph.edges(node) {
+            let alternative = distance + weight;
+            if alternative < search.distances[head as usize] {
+                search.distances[head as usize] = alternative;
+                search.predecessors[head as usize] = node;
+                search.queue.push(Reverse((alternative, head)));
+            }
+
+            let other_distance = other.distances[head as usize];
+            if other_distance != u32::MAX
+                && search.distances[head as usize] + other_distance < best.0
+            {
+                *best = (search.distances[head as usize] + other_distance, head);
+            }
+        }
+
+        true
+    }
+}
+
+impl<G: GraphView + ?Sized> PathFinding for BidirectionalDijkstra<'_, G> {
+    fn shortest_path(&self, source: u32, target: u32) -> Option<Route> {
+        self.shortest_path_between(&[(source, 0)], &[(target, 0)])
+    }
+
+    /// Runs the forward search from all sources and the backward search from all targets at
+    /// once, each starting at its offset.
+    fn shortest_path_between(
+        &self,
+        sources: &[(u32, u32)],
+        targets: &[(u32, u32)],
+    ) -> Option<Route> {
+        let num_nodes = self.graph.num_nodes() as usize;
+        let mut forward = Search::new(num_nodes, sources);
+        let mut backward = Search::new(num_nodes, targets);
+        let mut settled = 0;
+
+        // (distance, meeting node) of the shortest path found so far, starting with the nodes
+        // that are both a source and a target
+        let mut best = (u32::MAX, u32::MAX);
+        for &(source, _) in sources {
+            let backward_distance = backward.distances[source as usize];
+            if backward_distance != u32::MAX {
+                let distance = forward.distances[source as usize] + backward_distance;
+                best = best.min((distance, source));
+            }
+        }
+
+        // every path that is not found yet is at least as long as the sum of both minimal keys
+        loop {
+            let forward_key = forward.min_key();
+            let backward_key = backward.min_key();
+            if forward_key == u32::MAX
+                || backward_key == u32::MAX
+                || forward_key.saturating_add(backward_key) >= best.0
+            {
+                break;
+            }
+
+            let settled_node = if forward_key <= backward_key {
+                self.step(&mut forward, &backward, &mut best)
+            } else {
+                self.step(&mut backward, &forward, &mut best)
+            };
+            if settled_node {
+                settled += 1;
+            }
+        }
+
+        let (distance, meeting_node) = best;
+        if distance == u32::MAX {
+            return None;
+        }
+
+        let mut path = unwind_from_start(&forward.predecessors, meeting_node);
+        let mut backward_path = unwind_from_start(&backward.predecessors, meeting_node);
+        backward_path.pop();
+        path.extend(backward_path.into_iter().rev());
+
+        Some(Route {
+            path,
+            distance,
+            settled,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::routing::{
+        bidirectional_dijkstra::BidirectionalDijkstra,
+        dijkstra::Dijkstra,
+        route::PathFinding,
+        test_graph::{assert_path_matches_distance, test_graph},
+    };
+
+    #[test]
+    fn same_distance_as_dijkstra() {
+        let num_nodes = 50;
+        let graph = test_graph(2, num_nodes, 120);
+
+        let dijkstra = Dijkstra::new(&graph);
+        let bidirectional_dijkstra = BidirectionalDijkstra::new(&graph);
+        for source in 0..num_nodes {
+            for target in 0..num_nodes {
+                let expected = dijkstra.shortest_path(source, target);
+                let route = bidirectional_dijkstra.shortest_path(source, target);
+                assert_eq!(
+                    route.as_ref().map(|route| route.distance),
+                    expected.map(|route| route.distance)
+                );
+
+                if let Some(route) = route {
+                    assert_path_matches_distance(&graph, &route, source, target);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use ahash::AHashMap;
+use indicatif::ProgressBar;
+
+use super::{
+    adjacency_array::{AdjacencyArray, GraphId},
+    contraction_hierarchy::ContractionHierarchy,
+};
+
+/// Maximum number of nodes a witness search settles before it gives up. Giving up early only
+/// adds superfluous shortcuts, it never breaks correctness.
+const WITNESS_SEARCH_LIMIT: u32 = 500;
+
+/// Contracts the nodes of a graph one after another, ordered by their edge difference, and keeps
+/// the remaining graph up to date.
+pub struct Contractor {
+    graph: GraphId,
+    outgoing: Vec<AHashMap<u32, u32>>,
+    incoming: Vec<AHashMap<u32, u32>>,
+    middles: AHashMap<(u32, u32), u32>,
+    contracted: Vec<bool>,
+    contracted_neighbors: Vec<i64>,
+}
+
+impl Contractor {
+    pub fn new(graph: &AdjacencyArray) -> Contractor {
+        let num_nodes = graph.num_nodes() as usize;
+        let mut outgoing = vec![AHashMap::new(); num_nodes];
+        let mut incoming = vec![AHashMap::new(); num_nodes];
+
+        for tail in 0..graph.num_nodes() {
+            for (head, weight) in graph.edges(tail) {
+                if tail == head {
+                    continue;
+                }
+                let current: &mut u32 = outgoing[tail as usize].entry(head).or_insert(u32::MAX);
+                if weight < *current {
+                    *current = weight;
+                    incoming[head as usize].insert(tail, weight);
+                }
+            }
+        }
+
+        Contractor {
+            graph: graph.graph_id(),
+            outgoing,
+            incoming,
+            middles: AHashMap::new(),
+            contracted: vec![false; num_nodes],
+            contracted_neighbors: vec![0; num_nodes],
+        }
+    }
+
+    /// Contracts all nodes and returns the resulting hierarchy.
+    pub fn contract(mut self) -> ContractionHierarchy {
+        let num_nodes = self.outgoing.len();
+
+        println!("calculating initial node order");
+        let pb = ProgressBar::new(num_nodes as u64);
+        let mut priorities: Vec<i64> = (0..num_nodes as u32)
+            .map(|node| {
+                pb.inc(1);
+                self.priority(node)
+            })
+            .collect();
+        pb.finish();
+        let mut queue: BinaryHeap<_> = priorities
+            .iter()
+            .enumerate()
+            .map(|(node, &priority)| Reverse((priority, node as u32)))
+            .collect();
+
+        println!("contracting nodes");
+        let pb = ProgressBar::new(num_nodes as u64);
+        let mut ranks = vec![u32::MAX; num_nodes];
+        let mut upward_edges = Vec::new();
+        let mut downward_edges = Vec::new();
+        let mut rank = 0;
+
+        while let Some(Reverse((priority, node))) = queue.pop() {
+            if self.contracted[node as usize] || priority != priorities[node as usize] {
+                continue;
+            }
+
+            // lazy update, the priority may have changed since the node was inserted
+            let priority = self.priority(node);
+            if let Some(Reverse((next_priority, _))) = queue.peek() {
+                if priority > *next_priority {
+                    priorities[node as usize] = priority;
+                    queue.push(Reverse((priority, node)));
+                    continue;
+                }
+            }
+
+            ranks[node as usize] = rank;
+            rank += 1;
+            pb.inc(1);
+
+            upward_edges.extend(
+                self.outgoing[node as usize]
+                    .iter()
+                    .map(|(&head, &weight)| (node, head, weight)),
+            );
+            downward_edges.extend(
+                self.incoming[node as usize]
+                    .iter()
+                    .map(|(&tail, &weight)| (node, tail, weight)),
+            );
+
+            let neighbors = self.contract_node(node);
+            for neighbor in neighbors {
+                self.contracted_neighbors[neighbor as usize] += 1;
+                let priority = self.priority(neighbor);
+                priorities[neighbor as usize] = priority;
+                queue.push(Reverse((priority, neighbor)));
+            }
+        }
+        pb.finish();
+
+        let mut shortcuts: Vec<_> = self
+            .middles
+            .into_iter()
+            .map(|((tail, head), middle)| (tail, head, middle))
+            .collect();
+        shortcuts.sort_unstable();
+
+        println!(
+            "added {} shortcuts to {} edges",
+            shortcuts.len(),
+            upward_edges.len() + downward_edges.len() - shortcuts.len()
+        );
+
+        ContractionHierarchy::new(
+            self.graph,
+            ranks,
+            AdjacencyArray::from_edges(num_nodes as u32, &upward_edges),
+            AdjacencyArray::from_edges(num_nodes as u32, &downward_edges),
+            shortcuts,
+        )
+    }
+
+    /// Returns the priority of a node, lower priorities get contracted first.
+    fn priority(&self, node: u32) -> i64 {
+        let edge_difference = self.shortcuts(node).len() as i64
+            - self.outgoing[node as usize].len() as i64
+            - self.incoming[node as usize].len() as i64;
+        edge_difference + self.contracted_neighbors[node as usize]
+    }
+
+    /// Returns the (tail, head, weight) shortcuts needed to contract node.
+    fn shortcuts(&self, node: u32) -> Vec<(u32, u32, u32)> {
+        let mut shortcuts = Vec::new();
+
+        for (&tail, &in_weight) in self.incoming[node as usize].iter() {
+            let max_distance = self.outgoing[node as usize]
+                .iter()
+                .filter(|(&head, _)| head != tail)
+                .map(|(_, &out_weight)| in_weight + out_weight)
+                .max();
+            let Some(max_distance) = max_distance else {
+                continue;
+            };
+
+            let witnesses = self.witness_search(tail, node, max_distance);
+            for (&head, &out_weight) in self.outgoing[node as usize].iter() {
+                if head == tail {
+                    continue;
+                }
+                let weight = in_weight + out_weight;
+                let has_witness = witnesses
+                    .get(&head)
+                    .is_some_and(|&distance| distance <= weight);
+                if !has_witness {
+                    shortcuts.push((tail, head, weight));
+                }
+            }
+        }
+
+        shortcuts
+    }
+
+    /// Dijkstra search from source in the remaining graph that ignores the node to contract.
+    fn witness_search(&self, source: u32, ignore: u32, max_distance: u32) -> AHashMap<u32, u32> {
+        let mut distances = AHashMap::new();
+        let mut queue = BinaryHeap::new();
+        let mut settled = 0;
+
+        distances.insert(source, 0);
+        queue.push(Reverse((0, source)));
+
+        while let Some(Reverse((distance, node))) = queue.pop() {
+            if distance > distances[&node] {
+                continue;
+            }
+            settled += 1;
+            if distance > max_distance || settled > WITNESS_SEARCH_LIMIT {
+                break;
+            }
+
+            for (&head, &weight) in self.outgoing[node as usize].iter() {
+                if head == ignore {
+                    continue;
+                }
+                let alternative = distance + weight;
+                let current = distances.entry(head).or_insert(u32::MAX);
+                if alternative < *current {
+                    *current = alternative;
+                    queue.push(Reverse((alternative, head)));
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Removes node from the remaining graph, inserts the necessary shortcuts and returns the
+    /// neighbors of node.
+    fn contract_node(&mut self, node: u32) -> Vec<u32> {
+        for (tail, head, weight) in self.shortcuts(node) {
+            let current = self.outgoing[tail as usize]
+                .get(&head)
+                .cloned()
+                .unwrap_or(u32::MAX);
+            if weight < current {
+                self.outgoing[tail as usize].insert(head, weight);
+                self.incoming[head as usize].insert(tail, weight);
+                self.middles.insert((tail, head), node);
+            }
+        }
+
+        self.contracted[node as usize] = true;
+        let outgoing = std::mem::take(&mut self.outgoing[node as usize]);
+        let incoming = std::mem::take(&mut self.incoming[node as usize]);
+        for &head in outgoing.keys() {
+            self.incoming[head as usize].remove(&node);
+        }
+        for &tail in incoming.keys() {
+            self.outgoing[tail as usize].remove(&node);
+        }
+
+        let mut neighbors: Vec<_> = outgoing.keys().chain(incoming.keys()).cloned().collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+}
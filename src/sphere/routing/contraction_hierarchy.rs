@@ -0,0 +1,232 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    error::Error,
+    fs::File,
+    io::{BufReader, BufWriter},
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+    adjacency_array::{AdjacencyArray, GraphId},
+    contraction::Contractor,
+    route::{unwind_path, PathFinding, Route},
+};
+
+/// A contraction hierarchy. Every edge of the graph and every shortcut is stored exactly once,
+/// at the endpoint with the lower rank.
+#[derive(Serialize, Deserialize)]
+pub struct ContractionHierarchy {
+    /// The graph the hierarchy was computed for.
+    graph: GraphId,
+    ranks: Vec<u32>,
+    /// Edges (tail, head) with rank[tail] < rank[head], stored at tail.
+    upward: AdjacencyArray,
+    /// Edges (tail, head) with rank[tail] > rank[head], stored reversed at head.
+    downward: AdjacencyArray,
+    /// (tail, head, middle) of every shortcut, sorted by tail and head.
+    shortcuts: Vec<(u32, u32, u32)>,
+}
+
+impl ContractionHierarchy {
+    pub fn new(
+        graph: GraphId,
+        ranks: Vec<u32>,
+        upward: AdjacencyArray,
+        downward: AdjacencyArray,
+        shortcuts: Vec<(u32, u32, u32)>,
+    ) -> ContractionHierarchy {
+        ContractionHierarchy {
+            graph,
+            ranks,
+            upward,
+            downward,
+            shortcuts,
+        }
+    }
+
+    /// Contracts every node of the graph.
+    pub fn from_graph(graph: &AdjacencyArray) -> ContractionHierarchy {
+        Contractor::new(graph).contract()
+    }
+
+    pub fn from_file(path: &str) -> Result<ContractionHierarchy, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(bincode::deserialize_from(reader)?)
+    }
+
+    pub fn to_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(writer, self)?;
+        Ok(())
+    }
+
+    /// The graph the hierarchy was computed for.
+    pub fn graph_id(&self) -> GraphId {
+        self.graph
+    }
+
+    pub fn ranks(&self) -> &[u32] {
+        &self.ranks
+    }
+
+    /// Edges to nodes of higher rank, e.g. the graph of the forward search.
+    pub fn upward(&self) -> &AdjacencyArray {
+        &self.upward
+    }
+
+    /// Reversed edges from nodes of higher rank, e.g. the graph of the backward search.
+    pub fn downward(&self) -> &AdjacencyArray {
+        &self.downward
+    }
+
+    /// (tail, head, middle) of every shortcut, sorted by tail and head.
+    pub fn shortcuts(&self) -> &[(u32, u32, u32)] {
+        &self.shortcuts
+    }
+
+    /// Replaces every shortcut of a path by the two edges it bypasses until only edges of the
+    /// original graph are left.
+    pub fn unpack(&self, path: &[u32]) -> Vec<u32> {
+        unpack(&self.shortcuts, path)
+    }
+}
+
+/// Replaces every shortcut of a path by the two edges it bypasses until only edges of the
+/// original graph are left. Shortcuts are given as sorted (tail, head, middle) triples.
+pub fn unpack(shortcuts: &[(u32, u32, u32)], path: &[u32]) -> Vec<u32> {
+    let mut unpacked = Vec::with_capacity(path.len());
+    if let Some(&first) = path.first() {
+        unpacked.push(first);
+    }
+
+    for edge in path.windows(2) {
+        let mut stack = vec![(edge[0], edge[1])];
+        while let Some((tail, head)) = stack.pop() {
+            let middle = shortcuts
+                .binary_search_by_key(&(tail, head), |&(tail, head, _)| (tail, head))
+                .map(|index| shortcuts[index].2);
+            match middle {
+                Ok(middle) => {
+                    stack.push((middle, head));
+                    stack.push((tail, middle));
+                }
+                Err(_) => unpacked.push(head),
+            }
+        }
+    }
+
+    unpacked
+}
+
+impl PathFinding for ContractionHierarchy {
+    fn shortest_path(&self, source: u32, target: u32) -> Option<Route> {
+        let num_nodes = self.ranks.len();
+        let mut distances = [vec![u32::MAX; num_nodes], vec![u32::MAX; num_nodes]];
+        let mut predecessors = [vec![u32::MAX; num_nodes], vec![u32::MAX; num_nodes]];
+        let mut queues = [BinaryHeap::new(), BinaryHeap::new()];
+        let graphs = [&self.upward, &self.downward];
+        let mut settled = 0;
+
+        distances[0][source as usize] = 0;
+        distances[1][target as usize] = 0;
+        queues[0].push(Reverse((0, source)));
+        queues[1].push(Reverse((0, target)));
+
+        // (distance, meeting node) of the shortest path found so far
+        let mut best = (u32::MAX, u32::MAX);
+
+        // Both searches only go upward, so they cannot stop when their keys add up to the best
+        // distance. A direction is finished once its own smallest key exceeds the best distance.
+        loop {
+            let keys = [0, 1].map(|direction| {
+                queues[direction]
+                    .peek()
+                    .map(|Reverse((distance, _))| *distance)
+                    .filter(|&distance| distance < best.0)
+            });
+            let direction = match keys {
+                [None, None] => break,
+                [Some(forward), Some(backward)] if backward < forward => 1,
+                [None, Some(_)] => 1,
+                _ => 0,
+            };
+
+            let Reverse((distance, node)) = queues[direction].pop().unwrap();
+            if distance > distances[direction][node as usize] {
+                continue;
+            }
+            settled += 1;
+
+            let other_distance = distances[1 - direction][node as usize];
+            if other_distance != u32::MAX && distance + other_distance < best.0 {
+                best = (distance + other_distance, node);
+            }
+
+            for (head, weight) in graphs[direction].edges(node) {
+                let alternative = distance + weight;
+                if alternative < distances[direction][head as usize] {
+                    distances[direction][head as usize] = alternative;
+                    predecessors[direction][head as usize] = node;
+                    queues[direction].push(Reverse((alternative, head)));
+                }
+            }
+        }
+
+        let (distance, meeting_node) = best;
+        if distance == u32::MAX {
+            return None;
+        }
+
+        let mut path = unwind_path(&predecessors[0], source, meeting_node);
+        let mut backward_path = unwind_path(&predecessors[1], target, meeting_node);
+        backward_path.pop();
+        path.extend(backward_path.into_iter().rev());
+
+        Some(Route {
+            path: self.unpack(&path),
+            distance,
+            settled,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::routing::{
+        contraction_hierarchy::ContractionHierarchy,
+        dijkstra::Dijkstra,
+        route::PathFinding,
+        test_graph::{assert_path_matches_distance, test_graph},
+    };
+
+    #[test]
+    fn same_route_as_dijkstra() {
+        let num_nodes = 100;
+        let graph = test_graph(0, num_nodes, 250);
+
+        let dijkstra = Dijkstra::new(&graph);
+        let contraction_hierarchy = ContractionHierarchy::from_graph(&graph);
+        assert_eq!(contraction_hierarchy.graph_id(), graph.graph_id());
+        assert_ne!(
+            contraction_hierarchy.graph_id(),
+            test_graph(1, num_nodes, 250).graph_id()
+        );
+        for source in 0..num_nodes {
+            for target in 0..num_nodes {
+                let expected = dijkstra.shortest_path(source, target);
+                let route = contraction_hierarchy.shortest_path(source, target);
+                assert_eq!(
+                    route.as_ref().map(|route| route.distance),
+                    expected.map(|route| route.distance)
+                );
+
+                // the unpacked path has to consist of original edges only
+                if let Some(route) = route {
+                    assert_path_matches_distance(&graph, &route, source, target);
+                }
+            }
+        }
+    }
+}
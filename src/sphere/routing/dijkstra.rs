@@ -0,0 +1,186 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use super::{
+    adjacency_array::AdjacencyArray,
+    graph_view::GraphView,
+    route::{offset_of, unwind_from_start, PathFinding, Route},
+};
+
+/// Dijkstra's algorithm on any graph, e.g. an `AdjacencyArray` or a memory mapped graph.
+pub struct Dijkstra<'a, G: ?Sized = AdjacencyArray> {
+    graph: &'a G,
+}
+
+impl<'a, G: GraphView + ?Sized> Dijkstra<'a, G> {
+    pub fn new(graph: &'a G) -> Dijkstra<'a, G> {
+        Dijkstra { graph }
+    }
+
+    /// Searches the whole graph and returns the distances and predecessors of every node,
+    /// `u32::MAX` marks nodes that are not reachable.
+    pub fn one_to_all(&self, source: u32) -> (Vec<u32>, Vec<u32>) {
+        let num_nodes = self.graph.num_nodes() as usize;
+        let mut distances = vec![u32::MAX; num_nodes];
+        let mut predecessors = vec![u32::MAX; num_nodes];
+        let mut queue = BinaryHeap::new();
+
+        distances[source as usize] = 0;
+        queue.push(Reverse((0, source)));
+
+        while let Some(Reverse((distance, node))) = queue.pop() {
+            if distance > distances[node as usize] {
+                continue;
+            }
+
+            for (head, weight) in self.graph.edges(node) {
+                let alternative = distance + weight;
+                if alternative < distances[head as usize] {
+                    distances[head as usize] = alternative;
+                    predecessors[head as usize] = node;
+                    queue.push(Reverse((alternative, head)));
+                }
+            }
+        }
+
+        (distances, predecessors)
+    }
+
+    /// Returns every node whose distance from source is at most max_distance, together with its
+    /// distance, in the order they were settled.
+    pub fn within(&self, source: u32, max_distance: u32) -> Vec<(u32, u32)> {
+        let num_nodes = self.graph.num_nodes() as usize;
+        let mut distances = vec![u32::MAX; num_nodes];
+        let mut queue = BinaryHeap::new();
+        let mut settled = Vec::new();
+
+        distances[source as usize] = 0;
+        queue.push(Reverse((0, source)));
+
+        while let Some(Reverse((distance, node))) = queue.pop() {
+            if distance > distances[node as usize] {
+                continue;
+            }
+            if distance > max_distance {
+                break;
+            }
+            settled.push((node, distance));
+
+            for (head, weight) in self.graph.edges(node) {
+                let alternative = distance + weight;
+                if alternative < distances[head as usize] {
+                    distances[head as usize] = alternative;
+                    queue.push(Reverse((alternative, head)));
+                }
+            }
+        }
+
+        settled
+    }
+}
+
+impl<G: GraphView + ?Sized> Dijkstra<'_, G> {
+    /// Same as `shortest_path`, but the weight of every edge is replaced by
+    /// `weight(tail, head, weight)`, e.g. to penalize edges without copying the graph.
+    pub fn shortest_path_with(
+        &self,
+        source: u32,
+        target: u32,
+        weight: impl Fn(u32, u32, u32) -> u32,
+    ) -> Option<Route> {
+        self.search(&[(source, 0)], &[(target, 0)], weight)
+    }
+
+    /// Searches from all sources at once, each starting at its offset. Stops as soon as no node
+    /// left in the queue can improve on the best settled target plus its offset.
+    fn search(
+        &self,
+        sources: &[(u32, u32)],
+        targets: &[(u32, u32)],
+        weight: impl Fn(u32, u32, u32) -> u32,
+    ) -> Option<Route> {
+        let num_nodes = self.graph.num_nodes() as usize;
+        let mut distances = vec![u32::MAX; num_nodes];
+        let mut predecessors = vec![u32::MAX; num_nodes];
+        let mut queue = BinaryHeap::new();
+        let mut settled = 0;
+
+        for &(source, offset) in sources {
+            if offset < distances[source as usize] {
+                distances[source as usize] = offset;
+                queue.push(Reverse((offset, source)));
+            }
+        }
+
+        // (distance including the offset, target) of the best target settled so far
+        let mut best = (u32::MAX, u32::MAX);
+        while let Some(Reverse((distance, node))) = queue.pop() {
+            if distance >= best.0 {
+                break;
+            }
+            if distance > distances[node as usize] {
+                continue;
+            }
+            settled += 1;
+            if let Some(offset) = offset_of(node, targets) {
+                best = best.min((distance.saturating_add(offset), node));
+            }
+
+            for (head, edge_weight) in self.graph.edges(node) {
+                let alternative = distance.saturating_add(weight(node, head, edge_weight));
+                if alternative < distances[head as usize] {
+                    distances[head as usize] = alternative;
+                    predecessors[head as usize] = node;
+                    queue.push(Reverse((alternative, head)));
+                }
+            }
+        }
+
+        let (distance, target) = best;
+        (distance != u32::MAX).then(|| Route {
+            path: unwind_from_start(&predecessors, target),
+            distance,
+            settled,
+        })
+    }
+}
+
+impl<G: GraphView + ?Sized> PathFinding for Dijkstra<'_, G> {
+    fn shortest_path(&self, source: u32, target: u32) -> Option<Route> {
+        self.shortest_path_with(source, target, |_, _, weight| weight)
+    }
+
+    fn shortest_path_between(
+        &self,
+        sources: &[(u32, u32)],
+        targets: &[(u32, u32)],
+    ) -> Option<Route> {
+        self.search(sources, targets, |_, _, weight| weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::routing::{
+        adjacency_array::AdjacencyArray, dijkstra::Dijkstra, route::PathFinding,
+    };
+
+    #[test]
+    fn shortest_path() {
+        // 0 -> 1 -> 2 -> 3 is shorter than 0 -> 3, node 4 is not reachable
+        let graph = AdjacencyArray::from_edges(
+            5,
+            &[(0, 1, 1), (1, 2, 2), (2, 3, 3), (0, 3, 10), (4, 0, 1)],
+        );
+        let dijkstra = Dijkstra::new(&graph);
+
+        let route = dijkstra.shortest_path(0, 3).unwrap();
+        assert_eq!(route.path, vec![0, 1, 2, 3]);
+        assert_eq!(route.distance, 6);
+
+        let route = dijkstra.shortest_path(2, 2).unwrap();
+        assert_eq!(route.path, vec![2]);
+        assert_eq!(route.distance, 0);
+
+        assert!(dijkstra.shortest_path(0, 4).is_none());
+    }
+}
@@ -0,0 +1,133 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use ahash::AHashMap;
+
+use super::{
+    adjacency_array::AdjacencyArray, contraction_hierarchy::ContractionHierarchy,
+    dijkstra::Dijkstra, graph_view::GraphView,
+};
+
+/// Returns the distances in meters between every source and every target, `matrix[i][j]` is the
+/// distance from `sources[i]` to `targets[j]` or `None` if it is not reachable.
+///
+/// Uses the bucket algorithm: A backward upward search from every target stores its distances
+/// in buckets at the nodes it settles. A forward upward search from every source then only
+/// needs to scan the buckets of the nodes it settles.
+pub fn many_to_many(
+    hierarchy: &ContractionHierarchy,
+    sources: &[u32],
+    targets: &[u32],
+) -> Vec<Vec<Option<u32>>> {
+    let mut buckets: AHashMap<u32, Vec<(usize, u32)>> = AHashMap::new();
+    for (j, &target) in targets.iter().enumerate() {
+        for (node, distance) in upward_search(hierarchy.downward(), target) {
+            buckets.entry(node).or_default().push((j, distance));
+        }
+    }
+
+    sources
+        .iter()
+        .map(|&source| {
+            let mut row = vec![u32::MAX; targets.len()];
+            for (node, distance) in upward_search(hierarchy.upward(), source) {
+                if let Some(bucket) = buckets.get(&node) {
+                    for &(j, target_distance) in bucket {
+                        row[j] = row[j].min(distance + target_distance);
+                    }
+                }
+            }
+            row.into_iter()
+                .map(|distance| (distance != u32::MAX).then_some(distance))
+                .collect()
+        })
+        .collect()
+}
+
+/// Same as `many_to_many`, but without speedup data. Runs one search of the whole graph per
+/// source, or per target if there are fewer targets, which needs the graph to be symmetric as
+/// every graph written by `Fmi::to_gr_co_file` is.
+pub fn many_to_many_dijkstra(
+    graph: &(impl GraphView + ?Sized),
+    sources: &[u32],
+    targets: &[u32],
+) -> Vec<Vec<Option<u32>>> {
+    if targets.len() < sources.len() {
+        let transposed = many_to_many_dijkstra(graph, targets, sources);
+        return (0..sources.len())
+            .map(|i| transposed.iter().map(|row| row[i]).collect())
+            .collect();
+    }
+
+    let dijkstra = Dijkstra::new(graph);
+    sources
+        .iter()
+        .map(|&source| {
+            let (distances, _) = dijkstra.one_to_all(source);
+            targets
+                .iter()
+                .map(|&target| distances[target as usize])
+                .map(|distance| (distance != u32::MAX).then_some(distance))
+                .collect()
+        })
+        .collect()
+}
+
+/// Settles every node reachable from start in graph and returns their distances. Search spaces
+/// in the hierarchy are small, so the distances are kept in a hash map.
+fn upward_search(graph: &AdjacencyArray, start: u32) -> Vec<(u32, u32)> {
+    let mut distances = AHashMap::new();
+    let mut queue = BinaryHeap::new();
+    let mut settled = Vec::new();
+
+    distances.insert(start, 0);
+    queue.push(Reverse((0, start)));
+
+    while let Some(Reverse((distance, node))) = queue.pop() {
+        if distance > distances[&node] {
+            continue;
+        }
+        settled.push((node, distance));
+
+        for (head, weight) in graph.edges(node) {
+            let alternative = distance + weight;
+            let current = distances.entry(head).or_insert(u32::MAX);
+            if alternative < *current {
+                *current = alternative;
+                queue.push(Reverse((alternative, head)));
+            }
+        }
+    }
+
+    settled
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::sphere::routing::{
+        contraction_hierarchy::ContractionHierarchy,
+        distance_matrix::{many_to_many, many_to_many_dijkstra},
+        test_graph::test_graph,
+    };
+
+    #[test]
+    fn same_distances_as_dijkstra() {
+        let num_nodes = 100;
+        let graph = test_graph(4, num_nodes, 250);
+        let hierarchy = ContractionHierarchy::from_graph(&graph);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let sources: Vec<u32> = (0..10).map(|_| rng.gen_range(0..num_nodes)).collect();
+        let targets: Vec<u32> = (0..15).map(|_| rng.gen_range(0..num_nodes)).collect();
+        assert_eq!(
+            many_to_many(&hierarchy, &sources, &targets),
+            many_to_many_dijkstra(&graph, &sources, &targets)
+        );
+        // searches from the targets
+        assert_eq!(
+            many_to_many(&hierarchy, &targets, &sources),
+            many_to_many_dijkstra(&graph, &targets, &sources)
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::{error::Error, fmt};
+
+use crate::sphere::{
+    geometry::{arc::Arc, point::Point},
+    spatial_partition::{kd_tree::KdTree, polygon_spatial_partition::PolygonSpatialPartition},
+};
+
+use super::{adjacency_array::arc_weight, graph_view::Coordinates};
+
+/// An exact coordinate a route starts or ends at, which is not a node of the graph.
+#[derive(Clone)]
+pub struct Endpoint {
+    pub point: Point,
+    /// Nearby nodes that can be reached on a direct arc without touching land, together with the
+    /// length of that arc in meters.
+    pub connections: Vec<(u32, u32)>,
+}
+
+/// The reason a coordinate cannot be used as an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EndpointError {
+    /// The latitude is outside of [-90, 90] or the longitude is not finite.
+    InvalidCoordinate,
+    /// The coordinate lies on land.
+    OnLand,
+    /// Every arc to a nearby node crosses land.
+    Unconnected,
+}
+
+impl fmt::Display for EndpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EndpointError::InvalidCoordinate => write!(
+                f,
+                "latitude must be within [-90, 90] and longitude must be finite"
+            ),
+            EndpointError::OnLand => write!(f, "point lies on land"),
+            EndpointError::Unconnected => write!(f, "no node can be reached from point"),
+        }
+    }
+}
+
+impl Error for EndpointError {}
+
+impl Endpoint {
+    /// Connects a [lon, lat] coordinate to the nearest `num_candidates` nodes whose arc to it
+    /// does not collide with land.
+    pub fn new(
+        coordinates: &(impl Coordinates + ?Sized),
+        kd_tree: &KdTree,
+        planet_grid: &PolygonSpatialPartition,
+        coordinate: (f64, f64),
+        num_candidates: usize,
+    ) -> Result<Endpoint, EndpointError> {
+        let point = to_point(coordinate)?;
+        if planet_grid.is_on_polygon(&point) {
+            return Err(EndpointError::OnLand);
+        }
+
+        let connections: Vec<_> = kd_tree
+            .nearest_k(&point, num_candidates)
+            .into_iter()
+            .filter_map(|node| {
+                let arc = Arc::new(&point, &coordinates.point(node));
+                if planet_grid.check_collision(&arc) {
+                    return None;
+                }
+                Some((node, arc_weight(&arc)))
+            })
+            .collect();
+        if connections.is_empty() {
+            return Err(EndpointError::Unconnected);
+        }
+
+        Ok(Endpoint { point, connections })
+    }
+
+    /// An endpoint placed on the node nearest to a [lon, lat] coordinate, used if no planet is
+    /// available to check the arc to an exact coordinate.
+    pub fn nearest_node(
+        coordinates: &(impl Coordinates + ?Sized),
+        kd_tree: &KdTree,
+        coordinate: (f64, f64),
+    ) -> Result<Endpoint, EndpointError> {
+        let node = kd_tree
+            .nearest(&to_point(coordinate)?)
+            .ok_or(EndpointError::Unconnected)?;
+        Ok(Endpoint::at_node(coordinates, node))
+    }
+
+    /// An endpoint placed exactly on a node.
+    pub fn at_node(coordinates: &(impl Coordinates + ?Sized), node: u32) -> Endpoint {
+        Endpoint {
+            point: coordinates.point(node),
+            connections: vec![(node, 0)],
+        }
+    }
+}
+
+/// Converts a [lon, lat] coordinate to a point.
+fn to_point(coordinate: (f64, f64)) -> Result<Point, EndpointError> {
+    let (longitude, latitude) = coordinate;
+    if !(-90.0..=90.0).contains(&latitude) || !longitude.is_finite() {
+        return Err(EndpointError::InvalidCoordinate);
+    }
+    Ok(Point::from_coordinate(latitude, longitude))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::{
+        geometry::{point::Point, polygon::Polygon},
+        routing::endpoint::{Endpoint, EndpointError},
+        spatial_partition::{kd_tree::KdTree, polygon_spatial_partition::PolygonSpatialPartition},
+    };
+
+    #[test]
+    fn connect_around_an_island() {
+        let island = Polygon::new(vec![
+            Point::from_coordinate(-1.0, 1.0),
+            Point::from_coordinate(-1.0, 2.0),
+            Point::from_coordinate(1.0, 2.0),
+            Point::from_coordinate(1.0, 1.0),
+            Point::from_coordinate(-1.0, 1.0),
+        ]);
+        let mut planet_grid = PolygonSpatialPartition::new(100);
+        planet_grid.add_polygons(&vec![island]);
+
+        // one node west of the island, one behind it and one far away in the north
+        let points = vec![
+            Point::from_coordinate(0.0, -1.5),
+            Point::from_coordinate(0.0, 2.5),
+            Point::from_coordinate(5.0, 0.0),
+        ];
+        let kd_tree = KdTree::new(&points);
+
+        let endpoint =
+            Endpoint::new(points.as_slice(), &kd_tree, &planet_grid, (0.0, 0.0), 2).unwrap();
+        assert_eq!(endpoint.connections.len(), 1);
+        assert_eq!(endpoint.connections[0].0, 0);
+
+        let endpoint =
+            Endpoint::new(points.as_slice(), &kd_tree, &planet_grid, (0.0, 0.0), 3).unwrap();
+        let nodes: Vec<_> = endpoint.connections.iter().map(|&(node, _)| node).collect();
+        assert_eq!(nodes, vec![0, 2]);
+
+        assert_eq!(
+            Endpoint::new(points.as_slice(), &kd_tree, &planet_grid, (1.5, 0.0), 3).err(),
+            Some(EndpointError::OnLand)
+        );
+        assert_eq!(
+            Endpoint::new(points.as_slice(), &kd_tree, &planet_grid, (0.9, 0.0), 1).err(),
+            Some(EndpointError::Unconnected)
+        );
+
+        for coordinate in [
+            (0.0, 90.5),
+            (0.0, -91.0),
+            (0.0, f64::NAN),
+            (f64::INFINITY, 0.0),
+        ] {
+            assert_eq!(
+                Endpoint::new(points.as_slice(), &kd_tree, &planet_grid, coordinate, 3).err(),
+                Some(EndpointError::InvalidCoordinate)
+            );
+            assert_eq!(
+                Endpoint::nearest_node(points.as_slice(), &kd_tree, coordinate).err(),
+                Some(EndpointError::InvalidCoordinate)
+            );
+        }
+        let endpoint = Endpoint::nearest_node(points.as_slice(), &kd_tree, (-0.5, 90.0)).unwrap();
+        assert_eq!(endpoint.connections, vec![(2, 0)]);
+    }
+}
@@ -0,0 +1,41 @@
+use crate::sphere::geometry::point::Point;
+
+/// Read-only access to the edges of a graph. Implemented by `AdjacencyArray` and by the memory
+/// mapped `MappedGraph`, so that searches run on a mapped file without copying it.
+pub trait GraphView {
+    /// The outgoing edges of a node as (head, weight) pairs.
+    type Edges<'a>: Iterator<Item = (u32, u32)>
+    where
+        Self: 'a;
+
+    fn num_nodes(&self) -> u32;
+
+    /// Returns the outgoing edges of node as (head, weight) pairs.
+    fn edges(&self, node: u32) -> Self::Edges<'_>;
+
+    /// Returns the weight of the lightest edge from tail to head.
+    fn weight(&self, tail: u32, head: u32) -> Option<u32> {
+        self.edges(tail)
+            .filter(|&(edge_head, _)| edge_head == head)
+            .map(|(_, weight)| weight)
+            .min()
+    }
+}
+
+/// Read-only access to the positions of the nodes of a graph, node ids are indices. Implemented
+/// by slices of points and by the memory mapped `MappedGraph`.
+pub trait Coordinates {
+    fn num_points(&self) -> u32;
+
+    fn point(&self, node: u32) -> Point;
+}
+
+impl Coordinates for [Point] {
+    fn num_points(&self) -> u32 {
+        self.len() as u32
+    }
+
+    fn point(&self, node: u32) -> Point {
+        self[node as usize]
+    }
+}
@@ -0,0 +1,37 @@
+use crate::sphere::geometry::{
+    arc::Arc,
+    point::{radians_to_meter, Point},
+};
+
+use super::graph_view::Coordinates;
+
+/// A lower bound on the distance between two nodes, used to guide the A* search.
+pub trait Heuristic {
+    /// Returns a lower bound for the distance from node to target in meters.
+    fn lower_bound(&self, node: u32, target: u32) -> u32;
+}
+
+impl<T: Heuristic + ?Sized> Heuristic for &T {
+    fn lower_bound(&self, node: u32, target: u32) -> u32 {
+        (**self).lower_bound(node, target)
+    }
+}
+
+/// Uses the great circle distance between two nodes. As arc weights are the rounded up great
+/// circle distances, this is admissible and consistent.
+pub struct GreatCircle<'a, C: ?Sized = [Point]> {
+    points: &'a C,
+}
+
+impl<'a, C: Coordinates + ?Sized> GreatCircle<'a, C> {
+    pub fn new(points: &'a C) -> GreatCircle<'a, C> {
+        GreatCircle { points }
+    }
+}
+
+impl<C: Coordinates + ?Sized> Heuristic for GreatCircle<'_, C> {
+    fn lower_bound(&self, node: u32, target: u32) -> u32 {
+        let arc = Arc::new(&self.points.point(node), &self.points.point(target));
+        radians_to_meter(arc.central_angle()).floor() as u32
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufReader, BufWriter},
+};
+
+use indicatif::ProgressIterator;
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+    adjacency_array::{AdjacencyArray, GraphId},
+    contraction_hierarchy::{unpack, ContractionHierarchy},
+    route::{PathFinding, Route},
+};
+
+/// Hub labels derived from a contraction hierarchy. The forward label of a node holds the
+/// distances to the nodes it can reach going upward in the hierarchy, the backward label the
+/// distances from them. The shortest distance between s and t is the minimum over the hubs both
+/// labels have in common.
+#[derive(Serialize, Deserialize)]
+pub struct HubLabels {
+    /// The graph the hierarchy, and therefore the labels, were computed for.
+    graph: GraphId,
+    forward: Labels,
+    backward: Labels,
+    /// (tail, head, middle) of every shortcut of the hierarchy, needed to unpack paths.
+    shortcuts: Vec<(u32, u32, u32)>,
+}
+
+/// The labels of all nodes in one flat array. The label of node `v` is stored at the indices
+/// `offsets[v]..offsets[v + 1]`, its entries are sorted by hub.
+#[derive(Serialize, Deserialize, Default)]
+struct Labels {
+    offsets: Vec<u32>,
+    hubs: Vec<u32>,
+    distances: Vec<u32>,
+    /// The neighbor that comes next on the path from the node to the hub.
+    next: Vec<u32>,
+}
+
+/// A single label entry of (hub, distance, next).
+type Entry = (u32, u32, u32);
+
+impl Labels {
+    fn label(&self, node: u32) -> std::ops::Range<usize> {
+        self.offsets[node as usize] as usize..self.offsets[node as usize + 1] as usize
+    }
+
+    fn entry(&self, node: u32, hub: u32) -> Option<Entry> {
+        let range = self.label(node);
+        let index = self.hubs[range.clone()].binary_search(&hub).ok()?;
+        let index = range.start + index;
+        Some((self.hubs[index], self.distances[index], self.next[index]))
+    }
+
+    fn entries(&self, node: u32) -> impl Iterator<Item = Entry> + '_ {
+        self.label(node)
+            .map(|index| (self.hubs[index], self.distances[index], self.next[index]))
+    }
+
+    /// Follows the next pointers from node up to hub.
+    fn path(&self, node: u32, hub: u32) -> Vec<u32> {
+        let mut path = vec![node];
+        let mut node = node;
+        while node != hub {
+            node = self.entry(node, hub).unwrap().2;
+            path.push(node);
+        }
+        path
+    }
+
+    fn from_labels(labels: Vec<Vec<Entry>>) -> Labels {
+        let mut flat = Labels {
+            offsets: vec![0],
+            ..Default::default()
+        };
+        for label in labels {
+            for (hub, distance, next) in label {
+                flat.hubs.push(hub);
+                flat.distances.push(distance);
+                flat.next.push(next);
+            }
+            flat.offsets.push(flat.hubs.len() as u32);
+        }
+        flat
+    }
+}
+
+impl HubLabels {
+    /// Computes the labels top down in the order of the hierarchy. The label of a node is the
+    /// union of the labels of its upward neighbors, without the entries whose distance can be
+    /// undercut by a path through another hub.
+    pub fn from_contraction_hierarchy(hierarchy: &ContractionHierarchy) -> HubLabels {
+        let ranks = hierarchy.ranks();
+        let mut nodes: Vec<u32> = (0..ranks.len() as u32).collect();
+        nodes.sort_unstable_by_key(|&node| std::cmp::Reverse(ranks[node as usize]));
+
+        let mut forward: Vec<Vec<Entry>> = vec![Vec::new(); ranks.len()];
+        let mut backward: Vec<Vec<Entry>> = vec![Vec::new(); ranks.len()];
+
+        println!("computing labels");
+        for &node in nodes.iter().progress() {
+            let label = merge_labels(node, hierarchy.upward(), &forward);
+            forward[node as usize] = prune(label, &backward, true);
+            let label = merge_labels(node, hierarchy.downward(), &backward);
+            backward[node as usize] = prune(label, &forward, false);
+        }
+
+        let labels = HubLabels {
+            graph: hierarchy.graph_id(),
+            forward: Labels::from_labels(forward),
+            backward: Labels::from_labels(backward),
+            shortcuts: hierarchy.shortcuts().to_vec(),
+        };
+        println!(
+            "average label size is {:.1}",
+            labels.forward.hubs.len() as f64 / ranks.len().max(1) as f64
+        );
+        labels
+    }
+
+    pub fn from_file(path: &str) -> Result<HubLabels, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(bincode::deserialize_from(reader)?)
+    }
+
+    pub fn to_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(writer, self)?;
+        Ok(())
+    }
+
+    /// The graph the labels were computed for.
+    pub fn graph_id(&self) -> GraphId {
+        self.graph
+    }
+
+    /// Returns the shortest distance from source to target in meters.
+    pub fn distance(&self, source: u32, target: u32) -> Option<u32> {
+        self.hub(source, target).map(|(_, distance)| distance)
+    }
+
+    /// Returns the hub on the shortest path from source to target and the length of the path.
+    pub fn hub(&self, source: u32, target: u32) -> Option<(u32, u32)> {
+        let mut forward = self.forward.entries(source).peekable();
+        let mut backward = self.backward.entries(target).peekable();
+        let mut best = (u32::MAX, u32::MAX);
+
+        while let (
+            Some(&(forward_hub, forward_distance, _)),
+            Some(&(backward_hub, backward_distance, _)),
+        ) = (forward.peek(), backward.peek())
+        {
+            match forward_hub.cmp(&backward_hub) {
+                std::cmp::Ordering::Less => {
+                    forward.next();
+                }
+                std::cmp::Ordering::Greater => {
+                    backward.next();
+                }
+                std::cmp::Ordering::Equal => {
+                    let distance = forward_distance + backward_distance;
+                    if distance < best.1 {
+                        best = (forward_hub, distance);
+                    }
+                    forward.next();
+                    backward.next();
+                }
+            }
+        }
+
+        (best.1 != u32::MAX).then_some(best)
+    }
+}
+
+impl PathFinding for HubLabels {
+    fn shortest_path(&self, source: u32, target: u32) -> Option<Route> {
+        let (hub, distance) = self.hub(source, target)?;
+
+        let mut path = self.forward.path(source, hub);
+        let mut backward_path = self.backward.path(target, hub);
+        backward_path.pop();
+        path.extend(backward_path.into_iter().rev());
+
+        Some(Route {
+            path: unpack(&self.shortcuts, &path),
+            distance,
+            settled: 0,
+        })
+    }
+}
+
+/// Merges the labels of the neighbors of node into its label, keeping the shortest distance for
+/// every hub.
+fn merge_labels(node: u32, graph: &AdjacencyArray, labels: &[Vec<Entry>]) -> Vec<Entry> {
+    let mut label = vec![(node, 0, node)];
+    for (head, weight) in graph.edges(node) {
+        label.extend(
+            labels[head as usize]
+                .iter()
+                .map(|&(hub, distance, _)| (hub, distance + weight, head)),
+        );
+    }
+    label.sort_unstable();
+    label.dedup_by_key(|&mut (hub, _, _)| hub);
+    label
+}
+
+/// Removes the entries of label for which a shorter path via another hub exists. The opposite
+/// labels of all hubs are already known, as hubs have a higher rank.
+fn prune(label: Vec<Entry>, opposite: &[Vec<Entry>], is_forward: bool) -> Vec<Entry> {
+    label
+        .iter()
+        .filter(|&&(hub, distance, _)| {
+            let hub_label = &opposite[hub as usize];
+            let shortest = if is_forward {
+                label_distance(&label, hub_label)
+            } else {
+                label_distance(hub_label, &label)
+            };
+            shortest >= distance
+        })
+        .cloned()
+        .collect()
+}
+
+/// Returns the minimal distance over the common hubs of a forward and a backward label, or
+/// `u32::MAX` if they have no hub in common.
+fn label_distance(forward: &[Entry], backward: &[Entry]) -> u32 {
+    let (mut i, mut j) = (0, 0);
+    let mut best = u32::MAX;
+    while i < forward.len() && j < backward.len() {
+        match forward[i].0.cmp(&backward[j].0) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                let distance = forward[i].1 + backward[j].1;
+                best = best.min(distance);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::routing::{
+        contraction_hierarchy::ContractionHierarchy,
+        dijkstra::Dijkstra,
+        hub_labels::HubLabels,
+        route::PathFinding,
+        test_graph::{assert_path_matches_distance, test_graph},
+    };
+
+    #[test]
+    fn same_route_as_dijkstra() {
+        let num_nodes = 80;
+        let graph = test_graph(1, num_nodes, 200);
+
+        let dijkstra = Dijkstra::new(&graph);
+        let hierarchy = ContractionHierarchy::from_graph(&graph);
+        let hub_labels = HubLabels::from_contraction_hierarchy(&hierarchy);
+        assert_eq!(hub_labels.graph_id(), graph.graph_id());
+        for source in 0..num_nodes {
+            for target in 0..num_nodes {
+                let expected = dijkstra.shortest_path(source, target);
+                assert_eq!(
+                    hub_labels.distance(source, target),
+                    expected.map(|route| route.distance)
+                );
+
+                if let Some(route) = hub_labels.shortest_path(source, target) {
+                    assert_path_matches_distance(&graph, &route, source, target);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::sphere::geometry::{planet::Planet, point::Point, polygon::Polygon};
+
+use super::{
+    dijkstra::Dijkstra,
+    graph_view::{Coordinates, GraphView},
+};
+
+const METERS_PER_NAUTICAL_MILE: f64 = 1852.0;
+
+/// The part of the graph that is reachable from a source within a distance budget.
+pub struct Isochrone {
+    /// Reachable nodes and their distance from the source in meters.
+    pub nodes: Vec<(u32, u32)>,
+    /// Convex hull around the reachable nodes, `None` if they are not contained in the
+    /// hemisphere around the source or there are less than three of them.
+    pub hull: Option<Polygon>,
+}
+
+impl Isochrone {
+    pub fn new(
+        graph: &(impl GraphView + ?Sized),
+        coordinates: &(impl Coordinates + ?Sized),
+        source: u32,
+        max_distance: u32,
+    ) -> Isochrone {
+        let nodes = Dijkstra::new(graph).within(source, max_distance);
+        let points: Vec<_> = nodes
+            .iter()
+            .map(|&(node, _)| coordinates.point(node))
+            .collect();
+        let hull = convex_hull(&coordinates.point(source), &points);
+
+        Isochrone { nodes, hull }
+    }
+
+    /// Returns a planet holding the hull and, if requested, every reachable node.
+    pub fn to_planet(&self, coordinates: &(impl Coordinates + ?Sized), with_nodes: bool) -> Planet {
+        let mut planet = Planet::new();
+        planet.polygons.extend(self.hull.clone());
+        if with_nodes {
+            planet
+                .points
+                .extend(self.nodes.iter().map(|&(node, _)| coordinates.point(node)));
+        }
+        planet
+    }
+}
+
+/// Returns the distance in meters a vessel travels in the given time.
+pub fn travel_distance(hours: f64, knots: f64) -> u32 {
+    (hours * knots * METERS_PER_NAUTICAL_MILE).round() as u32
+}
+
+/// Computes the spherical convex hull of points with the monotone chain algorithm. The points are
+/// projected onto the plane tangent to center with a gnomonic projection, which maps great
+/// circles to straight lines, so the hull in the plane is the hull on the sphere.
+pub fn convex_hull(center: &Point, points: &[Point]) -> Option<Polygon> {
+    let center_vector = center.n_vector();
+    let mut east = Point::north_pole().n_vector().cross(center_vector);
+    if east.norm() < 1e-9 {
+        // the center is a pole, every direction is south or north
+        east = nalgebra::Vector3::new(0.0, 1.0, 0.0);
+    }
+    let east = east.normalize();
+    let north = center_vector.cross(&east);
+
+    let mut projected = Vec::with_capacity(points.len());
+    for point in points {
+        let vector = point.n_vector();
+        let dot = vector.dot(center_vector);
+        if dot <= 1e-6 {
+            return None;
+        }
+        projected.push((vector.dot(&east) / dot, vector.dot(&north) / dot, *point));
+    }
+    projected.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    projected.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
+    if projected.len() < 3 {
+        return None;
+    }
+
+    let cross = |o: &(f64, f64, Point), a: &(f64, f64, Point), b: &(f64, f64, Point)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+
+    let mut hull: Vec<(f64, f64, Point)> = Vec::with_capacity(2 * projected.len());
+    for pass in [projected.clone(), projected.into_iter().rev().collect()] {
+        let lower_len = hull.len();
+        for point in pass {
+            while hull.len() >= lower_len + 2
+                && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], &point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    if hull.len() < 3 {
+        return None;
+    }
+
+    // counterclockwise and closed, as required by GeoJSON
+    let mut outline: Vec<_> = hull.into_iter().map(|(_, _, point)| point).collect();
+    outline.push(outline[0]);
+    Some(Polygon::new(outline))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::{
+        geometry::{arc::Arc, point::Point},
+        graph::graph::Fmi,
+        routing::{adjacency_array::AdjacencyArray, isochrone::Isochrone},
+    };
+
+    #[test]
+    fn isochrone_of_grid() {
+        // 11x11 grid with a spacing of 0.1 degree around (0, 0)
+        let points: Vec<_> = (0..121)
+            .map(|i| {
+                Point::from_coordinate((i / 11) as f64 * 0.1 - 0.5, (i % 11) as f64 * 0.1 - 0.5)
+            })
+            .collect();
+        let mut arcs = Vec::new();
+        for from in points.iter() {
+            for to in points.iter() {
+                let arc = Arc::new(from, to);
+                if from != to && arc.central_angle() < 0.1_f64.to_radians() * 1.01 {
+                    arcs.push(arc);
+                }
+            }
+        }
+        let fmi = Fmi { points, arcs };
+        let graph = AdjacencyArray::from_fmi(&fmi);
+
+        // the center and its four neighbors are about 11 km apart
+        let isochrone = Isochrone::new(&graph, fmi.points.as_slice(), 60, 12_000);
+        assert_eq!(isochrone.nodes.len(), 5);
+        let hull = isochrone.hull.unwrap();
+        assert_eq!(hull.outline.len(), 5);
+
+        let isochrone = Isochrone::new(&graph, fmi.points.as_slice(), 60, 10_000_000);
+        assert_eq!(isochrone.nodes.len(), 121);
+        let hull = isochrone.hull.unwrap();
+        assert_eq!(hull.outline.len(), 5);
+    }
+}
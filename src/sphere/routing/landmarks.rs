@@ -0,0 +1,271 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufReader, BufWriter},
+};
+
+use clap::ValueEnum;
+use indicatif::ProgressBar;
+use rand::{rngs::ThreadRng, Rng};
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+    adjacency_array::{AdjacencyArray, GraphId},
+    dijkstra::Dijkstra,
+    heuristic::Heuristic,
+};
+
+/// Strategy used to place the landmarks.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum LandmarkSelection {
+    /// Picks random nodes.
+    Random,
+    /// Picks the node that is farthest away from all landmarks selected so far.
+    Farthest,
+    /// Picks a leaf of the shortest path tree of a random node whose subtree has the worst lower
+    /// bounds and contains no landmark yet.
+    Avoid,
+}
+
+/// Precomputed distances between every node and a small set of landmarks. By the triangle
+/// inequality |d(l, t) - d(l, v)| is a lower bound on d(v, t) for every landmark l.
+///
+/// Distances are computed from the landmarks only, so the graph needs to be symmetric, which is
+/// the case for every graph written by `Fmi::to_gr_co_file`.
+#[derive(Serialize, Deserialize)]
+pub struct Landmarks {
+    /// The graph the distances were computed for.
+    graph: GraphId,
+    landmarks: Vec<u32>,
+    /// `distances[node * landmarks.len() + i]` is the distance between node and landmark i.
+    distances: Vec<u32>,
+}
+
+impl Landmarks {
+    pub fn new(
+        graph: &AdjacencyArray,
+        num_landmarks: u32,
+        selection: LandmarkSelection,
+    ) -> Landmarks {
+        let dijkstra = Dijkstra::new(graph);
+        let mut rng = rand::thread_rng();
+        let num_nodes = graph.num_nodes();
+        let num_landmarks = num_landmarks.min(num_nodes);
+
+        let mut landmarks = Vec::new();
+        let mut tables: Vec<Vec<u32>> = Vec::new();
+        let pb = ProgressBar::new(num_landmarks as u64);
+        while landmarks.len() < num_landmarks as usize {
+            let landmark = match selection {
+                LandmarkSelection::Random => None,
+                LandmarkSelection::Farthest => farthest(&dijkstra, num_nodes, &tables, &mut rng),
+                LandmarkSelection::Avoid => {
+                    avoid(&dijkstra, num_nodes, &landmarks, &tables, &mut rng)
+                }
+            }
+            .filter(|landmark| !landmarks.contains(landmark))
+            .unwrap_or_else(|| random(graph, &landmarks, &mut rng));
+
+            let (distances, _) = dijkstra.one_to_all(landmark);
+            landmarks.push(landmark);
+            tables.push(distances);
+            pb.inc(1);
+        }
+        pb.finish();
+
+        let distances = (0..graph.num_nodes() as usize)
+            .flat_map(|node| tables.iter().map(move |table| table[node]))
+            .collect();
+
+        Landmarks {
+            graph: graph.graph_id(),
+            landmarks,
+            distances,
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Landmarks, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(bincode::deserialize_from(reader)?)
+    }
+
+    pub fn to_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(writer, self)?;
+        Ok(())
+    }
+
+    /// The graph the distances were computed for.
+    pub fn graph_id(&self) -> GraphId {
+        self.graph
+    }
+
+    pub fn landmarks(&self) -> &[u32] {
+        &self.landmarks
+    }
+
+    /// Returns the distances between node and every landmark.
+    fn table(&self, node: u32) -> &[u32] {
+        let start = node as usize * self.landmarks.len();
+        &self.distances[start..start + self.landmarks.len()]
+    }
+}
+
+impl Heuristic for Landmarks {
+    fn lower_bound(&self, node: u32, target: u32) -> u32 {
+        self.table(node)
+            .iter()
+            .zip(self.table(target))
+            .filter(|(&node, &target)| node != u32::MAX && target != u32::MAX)
+            .map(|(&node, &target)| node.abs_diff(target))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn random(graph: &AdjacencyArray, landmarks: &[u32], rng: &mut ThreadRng) -> u32 {
+    loop {
+        let node = rng.gen_range(0..graph.num_nodes());
+        if !landmarks.contains(&node) {
+            return node;
+        }
+    }
+}
+
+/// Returns the node which maximizes the distance to its nearest landmark. Without landmarks, the
+/// node farthest away from a random node is returned.
+fn farthest(
+    dijkstra: &Dijkstra,
+    num_nodes: u32,
+    tables: &[Vec<u32>],
+    rng: &mut ThreadRng,
+) -> Option<u32> {
+    let min_distances = if tables.is_empty() {
+        dijkstra.one_to_all(rng.gen_range(0..num_nodes)).0
+    } else {
+        (0..num_nodes as usize)
+            .map(|node| tables.iter().map(|table| table[node]).min().unwrap())
+            .collect()
+    };
+
+    min_distances
+        .iter()
+        .enumerate()
+        .filter(|(_, &distance)| distance != u32::MAX && distance > 0)
+        .max_by_key(|(_, &distance)| distance)
+        .map(|(node, _)| node as u32)
+}
+
+/// The avoid heuristic of Goldberg and Werneck. The subtree of a node in the shortest path tree
+/// of a random root is weighted by how much the current landmarks underestimate the distance
+/// between the root and its nodes. Starting at the heaviest subtree without a landmark, the
+/// heaviest children are followed down to a leaf, which becomes the new landmark.
+fn avoid(
+    dijkstra: &Dijkstra,
+    num_nodes: u32,
+    landmarks: &[u32],
+    tables: &[Vec<u32>],
+    rng: &mut ThreadRng,
+) -> Option<u32> {
+    if tables.is_empty() {
+        return farthest(dijkstra, num_nodes, tables, rng);
+    }
+
+    let root = rng.gen_range(0..num_nodes);
+    let (distances, predecessors) = dijkstra.one_to_all(root);
+
+    let lower_bound = |node: usize| {
+        tables
+            .iter()
+            .filter(|table| table[root as usize] != u32::MAX && table[node] != u32::MAX)
+            .map(|table| table[root as usize].abs_diff(table[node]))
+            .max()
+            .unwrap_or(0)
+    };
+
+    let num_nodes = num_nodes as usize;
+    let mut nodes: Vec<usize> = (0..num_nodes)
+        .filter(|&node| distances[node] != u32::MAX)
+        .collect();
+    nodes.sort_unstable_by_key(|&node| std::cmp::Reverse(distances[node]));
+
+    let mut sizes = vec![0_u64; num_nodes];
+    let mut has_landmark = vec![false; num_nodes];
+    let mut heaviest_child = vec![u32::MAX; num_nodes];
+    landmarks
+        .iter()
+        .for_each(|&landmark| has_landmark[landmark as usize] = true);
+
+    // children are farther away from the root than their parent, so they are finished first
+    for &node in nodes.iter() {
+        sizes[node] += distances[node].saturating_sub(lower_bound(node)) as u64;
+        if has_landmark[node] {
+            sizes[node] = 0;
+        }
+
+        let parent = predecessors[node];
+        if parent == u32::MAX {
+            continue;
+        }
+        let parent = parent as usize;
+        has_landmark[parent] |= has_landmark[node];
+        sizes[parent] += sizes[node];
+        let heaviest = heaviest_child[parent];
+        if heaviest == u32::MAX || sizes[heaviest as usize] < sizes[node] {
+            heaviest_child[parent] = node as u32;
+        }
+    }
+
+    let mut node = nodes
+        .iter()
+        .cloned()
+        .filter(|&node| sizes[node] > 0)
+        .max_by_key(|&node| sizes[node])? as u32;
+    while heaviest_child[node as usize] != u32::MAX
+        && sizes[heaviest_child[node as usize] as usize] > 0
+    {
+        node = heaviest_child[node as usize];
+    }
+
+    Some(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::routing::{
+        a_star::AStar,
+        dijkstra::Dijkstra,
+        landmarks::{LandmarkSelection, Landmarks},
+        route::PathFinding,
+        test_graph::test_graph,
+    };
+
+    #[test]
+    fn same_distance_as_dijkstra() {
+        let num_nodes = 60;
+        let graph = test_graph(3, num_nodes, 150);
+        let dijkstra = Dijkstra::new(&graph);
+
+        for selection in [
+            LandmarkSelection::Random,
+            LandmarkSelection::Farthest,
+            LandmarkSelection::Avoid,
+        ] {
+            let landmarks = Landmarks::new(&graph, 4, selection);
+            assert_eq!(landmarks.landmarks().len(), 4);
+            assert_eq!(landmarks.graph_id(), graph.graph_id());
+
+            let alt = AStar::new(&graph, &landmarks);
+            for source in 0..num_nodes {
+                for target in 0..num_nodes {
+                    let expected = dijkstra.shortest_path(source, target);
+                    let route = alt.shortest_path(source, target);
+                    assert_eq!(
+                        route.map(|route| route.distance),
+                        expected.map(|route| route.distance)
+                    );
+                }
+            }
+        }
+    }
+}
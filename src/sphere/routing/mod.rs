@@ -0,0 +1,19 @@
+pub mod a_star;
+pub mod adjacency_array;
+pub mod alternatives;
+pub mod bidirectional_dijkstra;
+pub mod contraction;
+pub mod contraction_hierarchy;
+pub mod dijkstra;
+pub mod distance_matrix;
+pub mod endpoint;
+pub mod graph_view;
+pub mod heuristic;
+pub mod hub_labels;
+pub mod isochrone;
+pub mod landmarks;
+pub mod route;
+pub mod smoothing;
+#[cfg(test)]
+pub mod test_graph;
+pub mod tour;
@@ -0,0 +1,225 @@
+/// The result of a shortest path query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Route {
+    /// Node ids from source to target, both inclusive.
+    pub path: Vec<u32>,
+    /// Length of the route in meters.
+    pub distance: u32,
+    /// Number of nodes the search settled, zero for lookups that do not search at all.
+    pub settled: u32,
+}
+
+/// A point to point shortest path search.
+pub trait PathFinding {
+    /// Returns the shortest route from source to target, or `None` if target is not reachable.
+    fn shortest_path(&self, source: u32, target: u32) -> Option<Route>;
+
+    /// Returns one route per leg between consecutive waypoints, or `None` if any leg has no
+    /// route.
+    fn legs(&self, waypoints: &[u32]) -> Option<Vec<Route>> {
+        waypoints
+            .windows(2)
+            .map(|leg| self.shortest_path(leg[0], leg[1]))
+            .collect()
+    }
+
+    /// Returns the shortest route from any source to any target, given as (node, offset) pairs.
+    /// The offsets are added to the length of the route, e.g. the distance between an exact
+    /// coordinate and a nearby node. The default implementation searches every pair, which the
+    /// searches without speedup data replace by one search from all sources at once.
+    fn shortest_path_between(
+        &self,
+        sources: &[(u32, u32)],
+        targets: &[(u32, u32)],
+    ) -> Option<Route> {
+        let mut best: Option<Route> = None;
+        let mut settled = 0;
+        for &(source, source_offset) in sources {
+            for &(target, target_offset) in targets {
+                let Some(mut route) = self.shortest_path(source, target) else {
+                    continue;
+                };
+                settled += route.settled;
+                route.distance += source_offset + target_offset;
+                if best.as_ref().map_or(u32::MAX, |best| best.distance) > route.distance {
+                    best = Some(route);
+                }
+            }
+        }
+        best.map(|route| Route { settled, ..route })
+    }
+}
+
+impl Route {
+    /// Joins the legs of a multi-leg route into one route. The target of every leg has to be the
+    /// source of the next one.
+    pub fn concat(legs: &[Route]) -> Option<Route> {
+        let (first, rest) = legs.split_first()?;
+        let mut route = first.clone();
+        for leg in rest {
+            route.path.extend(leg.path.iter().skip(1));
+            route.distance += leg.distance;
+            route.settled += leg.settled;
+        }
+        Some(route)
+    }
+}
+
+impl<T: PathFinding + ?Sized> PathFinding for &T {
+    fn shortest_path(&self, source: u32, target: u32) -> Option<Route> {
+        (**self).shortest_path(source, target)
+    }
+}
+
+/// Follows the predecessors from target back to source and returns the path from source to
+/// target.
+pub fn unwind_path(predecessors: &[u32], source: u32, target: u32) -> Vec<u32> {
+    let mut path = vec![target];
+    let mut node = target;
+    while node != source {
+        node = predecessors[node as usize];
+        path.push(node);
+    }
+    path.reverse();
+    path
+}
+
+/// Follows the predecessors from target back to the node without predecessor the search started
+/// at, and returns the path from there to target. Used by searches from several sources.
+pub fn unwind_from_start(predecessors: &[u32], target: u32) -> Vec<u32> {
+    let mut path = vec![target];
+    let mut node = target;
+    while predecessors[node as usize] != u32::MAX {
+        node = predecessors[node as usize];
+        path.push(node);
+    }
+    path.reverse();
+    path
+}
+
+/// Returns the lowest offset of node among the (node, offset) pairs, `None` if it is not one of
+/// them.
+pub fn offset_of(node: u32, nodes: &[(u32, u32)]) -> Option<u32> {
+    nodes
+        .iter()
+        .filter(|&&(other, _)| other == node)
+        .map(|&(_, offset)| offset)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::sphere::routing::{
+        a_star::AStar,
+        adjacency_array::AdjacencyArray,
+        bidirectional_dijkstra::BidirectionalDijkstra,
+        dijkstra::Dijkstra,
+        landmarks::{LandmarkSelection, Landmarks},
+        route::{offset_of, PathFinding, Route},
+        test_graph::{assert_path_matches_distance, test_graph},
+    };
+
+    #[test]
+    fn multi_leg_route() {
+        // a path 0 - 1 - 2 - 3
+        let graph = AdjacencyArray::from_edges(
+            4,
+            &[
+                (0, 1, 1),
+                (1, 0, 1),
+                (1, 2, 2),
+                (2, 1, 2),
+                (2, 3, 3),
+                (3, 2, 3),
+            ],
+        );
+        let dijkstra = Dijkstra::new(&graph);
+
+        let legs = dijkstra.legs(&[0, 2, 1, 3]).unwrap();
+        let distances: Vec<_> = legs.iter().map(|leg| leg.distance).collect();
+        assert_eq!(distances, vec![3, 2, 5]);
+
+        let route = Route::concat(&legs).unwrap();
+        assert_eq!(route.path, vec![0, 1, 2, 1, 2, 3]);
+        assert_eq!(route.distance, 10);
+
+        assert!(Route::concat(&dijkstra.legs(&[0]).unwrap()).is_none());
+    }
+
+    #[test]
+    fn shortest_path_between_offsets() {
+        // a path 0 - 1 - 2 - 3
+        let graph = AdjacencyArray::from_edges(
+            4,
+            &[
+                (0, 1, 1),
+                (1, 0, 1),
+                (1, 2, 2),
+                (2, 1, 2),
+                (2, 3, 3),
+                (3, 2, 3),
+            ],
+        );
+        let dijkstra = Dijkstra::new(&graph);
+
+        let route = dijkstra
+            .shortest_path_between(&[(0, 10), (1, 1)], &[(3, 0), (2, 5)])
+            .unwrap();
+        assert_eq!(route.path, vec![1, 2, 3]);
+        assert_eq!(route.distance, 6);
+
+        assert!(dijkstra.shortest_path_between(&[], &[(3, 0)]).is_none());
+    }
+
+    #[test]
+    fn shortest_path_between_same_as_every_pair() {
+        let num_nodes = 60;
+        let graph = test_graph(4, num_nodes, 150);
+        let dijkstra = &Dijkstra::new(&graph);
+        let landmarks = Landmarks::new(&graph, 4, LandmarkSelection::Farthest);
+        let searches: Vec<Box<dyn PathFinding>> = vec![
+            Box::new(Dijkstra::new(&graph)),
+            Box::new(AStar::new(&graph, &landmarks)),
+            Box::new(BidirectionalDijkstra::new(&graph)),
+        ];
+
+        // up to four (node, offset) candidates
+        let candidates = |rng: &mut StdRng| -> Vec<(u32, u32)> {
+            (0..rng.gen_range(1..5))
+                .map(|_| (rng.gen_range(0..num_nodes), rng.gen_range(0..50)))
+                .collect()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let sources = candidates(&mut rng);
+            let targets = candidates(&mut rng);
+            let expected = sources
+                .iter()
+                .flat_map(|&(source, source_offset)| {
+                    targets.iter().filter_map(move |&(target, target_offset)| {
+                        let route = dijkstra.shortest_path(source, target)?;
+                        Some(route.distance + source_offset + target_offset)
+                    })
+                })
+                .min();
+
+            for search in &searches {
+                let route = search.shortest_path_between(&sources, &targets);
+                assert_eq!(route.as_ref().map(|route| route.distance), expected);
+                if let Some(route) = route {
+                    let source = route.path[0];
+                    let target = *route.path.last().unwrap();
+                    let offsets =
+                        offset_of(source, &sources).unwrap() + offset_of(target, &targets).unwrap();
+                    let route = Route {
+                        distance: route.distance - offsets,
+                        ..route
+                    };
+                    assert_path_matches_distance(&graph, &route, source, target);
+                }
+            }
+        }
+    }
+}
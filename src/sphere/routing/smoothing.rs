@@ -0,0 +1,67 @@
+use crate::sphere::{
+    geometry::{arc::Arc, point::Point},
+    spatial_partition::polygon_spatial_partition::PolygonSpatialPartition,
+};
+
+/// Removes the zig-zag of a route by greedily replacing runs of points with a single great
+/// circle arc. Starting at the first point, the route is followed as long as the direct arc from
+/// that point does not collide with land, the last point reached that way becomes the start of the
+/// next arc. Consecutive points of the route need to be connected by collision free arcs, as it is
+/// the case for every arc of the graph.
+pub fn smooth(points: &[Point], planet_grid: &PolygonSpatialPartition) -> Vec<Point> {
+    let mut smoothed = Vec::new();
+    let Some(&first) = points.first() else {
+        return smoothed;
+    };
+    smoothed.push(first);
+
+    let mut anchor = 0;
+    while anchor + 1 < points.len() {
+        let mut next = anchor + 1;
+        while next + 1 < points.len()
+            && !planet_grid.check_collision(&Arc::new(&points[anchor], &points[next + 1]))
+        {
+            next += 1;
+        }
+        smoothed.push(points[next]);
+        anchor = next;
+    }
+
+    smoothed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::{
+        geometry::{point::Point, polygon::Polygon},
+        routing::smoothing::smooth,
+        spatial_partition::polygon_spatial_partition::PolygonSpatialPartition,
+    };
+
+    #[test]
+    fn around_an_island() {
+        let island = Polygon::new(vec![
+            Point::from_coordinate(-1.0, 1.5),
+            Point::from_coordinate(-1.0, 2.5),
+            Point::from_coordinate(0.8, 2.5),
+            Point::from_coordinate(0.8, 1.5),
+            Point::from_coordinate(-1.0, 1.5),
+        ]);
+        let mut planet_grid = PolygonSpatialPartition::new(100);
+        planet_grid.add_polygons(&vec![island]);
+
+        // a detour to the north of the island
+        let points = vec![
+            Point::from_coordinate(0.0, 0.0),
+            Point::from_coordinate(2.0, 1.0),
+            Point::from_coordinate(2.0, 2.0),
+            Point::from_coordinate(2.0, 3.0),
+            Point::from_coordinate(0.0, 4.0),
+        ];
+        let smoothed = smooth(&points, &planet_grid);
+        assert!(smoothed == vec![points[0], points[3], points[4]]);
+
+        assert!(smooth(&points[..1], &planet_grid) == vec![points[0]]);
+        assert!(smooth(&[], &planet_grid).is_empty());
+    }
+}
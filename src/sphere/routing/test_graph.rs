@@ -0,0 +1,43 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::sphere::routing::{adjacency_array::AdjacencyArray, route::Route};
+
+/// Returns a random symmetric graph with num_nodes nodes and num_edges edges in both directions,
+/// weighted between 1 and 99. The same seed gives the same graph, so failures can be reproduced.
+pub fn test_graph(seed: u64, num_nodes: u32, num_edges: u32) -> AdjacencyArray {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut edges = Vec::new();
+    for _ in 0..num_edges {
+        let tail = rng.gen_range(0..num_nodes);
+        let head = rng.gen_range(0..num_nodes);
+        let weight = rng.gen_range(1..100);
+        edges.push((tail, head, weight));
+        edges.push((head, tail, weight));
+    }
+    AdjacencyArray::from_edges(num_nodes, &edges)
+}
+
+/// Asserts that route runs from source to target over edges of graph, and that the lightest of
+/// these edges add up to the distance of the route.
+pub fn assert_path_matches_distance(
+    graph: &AdjacencyArray,
+    route: &Route,
+    source: u32,
+    target: u32,
+) {
+    assert_eq!(route.path.first(), Some(&source));
+    assert_eq!(route.path.last(), Some(&target));
+    let length: u32 = route
+        .path
+        .windows(2)
+        .map(|edge| {
+            graph
+                .edges(edge[0])
+                .filter(|&(head, _)| head == edge[1])
+                .map(|(_, weight)| weight)
+                .min()
+                .unwrap_or_else(|| panic!("no edge from {} to {}", edge[0], edge[1]))
+        })
+        .sum();
+    assert_eq!(length, route.distance);
+}
@@ -0,0 +1,178 @@
+/// Cost of a leg without a route. Large enough that every order with a route is shorter, small
+/// enough that sums over many legs do not overflow.
+const UNREACHABLE: u64 = u32::MAX as u64;
+
+/// Returns a short order in which to visit every port, as indices into the distance matrix. The
+/// order starts at start and ends at end if they are given, the route is a round trip if both are
+/// the same port. Returns `None` if there is no order in which every leg has a route.
+///
+/// The order is built with the nearest neighbor heuristic and then improved with 2-opt and
+/// Or-opt moves until neither finds a shorter order. The distances need to be symmetric, as 2-opt
+/// reverses parts of the order.
+pub fn visiting_order(
+    distances: &[Vec<Option<u32>>],
+    start: Option<usize>,
+    end: Option<usize>,
+) -> Option<Vec<usize>> {
+    let free: Vec<usize> = (0..distances.len())
+        .filter(|&port| Some(port) != start && Some(port) != end)
+        .collect();
+
+    let first_ports = match start {
+        Some(start) => vec![start],
+        None => free.clone(),
+    };
+    // without a start, the end can be the only port
+    if first_ports.is_empty() {
+        return Some(end.into_iter().collect());
+    }
+    let mut order = first_ports
+        .into_iter()
+        .map(|first| {
+            let mut order = vec![first];
+            let mut unvisited: Vec<usize> = free.iter().cloned().filter(|&p| p != first).collect();
+            while !unvisited.is_empty() {
+                let last = *order.last().unwrap();
+                let (index, _) = unvisited
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, &port)| cost(distances, Some(last), Some(port)))
+                    .unwrap();
+                order.push(unvisited.swap_remove(index));
+            }
+            order.extend(end);
+            order
+        })
+        .min_by_key(|order| length(distances, order))?;
+
+    // the first and the last port stay in place if they are fixed
+    let low = start.is_some() as usize;
+    let high = order.len() - end.is_some() as usize;
+    while two_opt(distances, &mut order, low, high) || or_opt(distances, &mut order, low, high) {}
+
+    (length(distances, &order) < UNREACHABLE).then_some(order)
+}
+
+/// Reverses the first part of the order whose reversal shortens it. Returns false if there is
+/// none.
+fn two_opt(distances: &[Vec<Option<u32>>], order: &mut [usize], low: usize, high: usize) -> bool {
+    for i in low..high {
+        for j in i + 1..high {
+            let before = neighbor(order, i, -1);
+            let after = neighbor(order, j, 1);
+            let removed =
+                cost(distances, before, Some(order[i])) + cost(distances, Some(order[j]), after);
+            let added =
+                cost(distances, before, Some(order[j])) + cost(distances, Some(order[i]), after);
+            if added < removed {
+                order[i..=j].reverse();
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Moves the first segment of up to three ports whose move to another position shortens the
+/// order. Returns false if there is none.
+fn or_opt(distances: &[Vec<Option<u32>>], order: &mut Vec<usize>, low: usize, high: usize) -> bool {
+    for segment_len in 1..=3 {
+        for i in low..(high + 1).saturating_sub(segment_len) {
+            let last = i + segment_len - 1;
+            let before = neighbor(order, i, -1);
+            let after = neighbor(order, last, 1);
+            let removed =
+                cost(distances, before, Some(order[i])) + cost(distances, Some(order[last]), after);
+            let bridged = cost(distances, before, after);
+
+            // insert between the ports at k - 1 and k
+            for k in low..=high {
+                if (i..=last + 1).contains(&k) {
+                    continue;
+                }
+                let previous = k.checked_sub(1).map(|k| order[k]);
+                let next = order.get(k).cloned();
+                let added = cost(distances, previous, Some(order[i]))
+                    + cost(distances, Some(order[last]), next);
+                if bridged + added < removed + cost(distances, previous, next) {
+                    let segment: Vec<_> = order.drain(i..=last).collect();
+                    let k = if k > i { k - segment_len } else { k };
+                    order.splice(k..k, segment);
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Returns the port next to index in direction, `None` at the ends of the order.
+fn neighbor(order: &[usize], index: usize, direction: isize) -> Option<usize> {
+    let index = index.checked_add_signed(direction)?;
+    order.get(index).cloned()
+}
+
+/// Returns the distance between two ports. A missing port, e.g. the one before the first port,
+/// costs nothing.
+fn cost(distances: &[Vec<Option<u32>>], from: Option<usize>, to: Option<usize>) -> u64 {
+    match (from, to) {
+        (Some(from), Some(to)) => distances[from][to].map_or(UNREACHABLE, |d| d as u64),
+        _ => 0,
+    }
+}
+
+fn length(distances: &[Vec<Option<u32>>], order: &[usize]) -> u64 {
+    order
+        .windows(2)
+        .map(|leg| cost(distances, Some(leg[0]), Some(leg[1])))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::routing::tour::visiting_order;
+
+    #[test]
+    fn ports_on_a_line() {
+        let positions: [i64; 6] = [0, 50, 20, 80, 30, 60];
+        let distances: Vec<Vec<_>> = positions
+            .iter()
+            .map(|a| {
+                positions
+                    .iter()
+                    .map(|b| Some(a.abs_diff(*b) as u32))
+                    .collect()
+            })
+            .collect();
+
+        let order = visiting_order(&distances, None, None).unwrap();
+        assert!(order == vec![0, 2, 4, 1, 5, 3] || order == vec![3, 5, 1, 4, 2, 0]);
+
+        // starting in the middle, it is best to go down first
+        let order = visiting_order(&distances, Some(4), None).unwrap();
+        assert_eq!(order, vec![4, 2, 0, 1, 5, 3]);
+
+        let order = visiting_order(&distances, Some(2), Some(5)).unwrap();
+        assert_eq!(order, vec![2, 0, 4, 1, 3, 5]);
+
+        // a round trip
+        let order = visiting_order(&distances, Some(1), Some(1)).unwrap();
+        assert_eq!(order.len(), 7);
+        assert_eq!((order[0], order[6]), (1, 1));
+
+        // port 1 cannot be reached
+        let mut distances = distances;
+        distances.iter_mut().for_each(|row| row[1] = None);
+        distances[1] = vec![None; positions.len()];
+        assert!(visiting_order(&distances, None, None).is_none());
+    }
+
+    #[test]
+    fn only_end() {
+        assert_eq!(
+            visiting_order(&[vec![Some(0)]], None, Some(0)),
+            Some(vec![0])
+        );
+        assert_eq!(visiting_order(&[], None, None), Some(vec![]));
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    error::Error,
+    f64::consts::PI,
+    fs::File,
+    io::{BufReader, BufWriter},
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::sphere::{
+    geometry::point::{meters_to_radians, Point},
+    routing::graph_view::Coordinates,
+};
+
+/// A k-d tree over the n-vectors of points. The euclidean distance between two n-vectors is the
+/// chord between the points, which grows with their central angle, so the points nearest in
+/// space are the points nearest on the sphere.
+///
+/// The tree is stored implicitly: the middle entry of every range is the splitting entry of that
+/// range, the entries before it are not above it and the entries after it are not below it on
+/// the axis of the level.
+#[derive(Serialize, Deserialize)]
+pub struct KdTree {
+    ids: Vec<u32>,
+    n_vectors: Vec<[f64; 3]>,
+}
+
+impl KdTree {
+    /// Builds the tree, the id of a point is its index.
+    pub fn new(points: &[Point]) -> KdTree {
+        KdTree::from_coordinates(points)
+    }
+
+    /// Builds the tree over the nodes of a graph, e.g. read from a memory mapped graph in place.
+    pub fn from_coordinates(coordinates: &(impl Coordinates + ?Sized)) -> KdTree {
+        let mut entries: Vec<(u32, [f64; 3])> = (0..coordinates.num_points())
+            .map(|id| (id, query(&coordinates.point(id))))
+            .collect();
+        build(&mut entries, 0);
+        let (ids, n_vectors) = entries.into_iter().unzip();
+        KdTree { ids, n_vectors }
+    }
+
+    pub fn from_file(path: &str) -> Result<KdTree, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(bincode::deserialize_from(reader)?)
+    }
+
+    pub fn to_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(writer, self)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns true if the tree holds exactly the points, each under its index. Used to check
+    /// that a tree read from a file belongs to the graph it is loaded with.
+    pub fn is_built_from(&self, coordinates: &(impl Coordinates + ?Sized)) -> bool {
+        self.len() == coordinates.num_points() as usize
+            && self.ids.iter().zip(&self.n_vectors).all(|(&id, n_vector)| {
+                id < coordinates.num_points() && query(&coordinates.point(id)) == *n_vector
+            })
+    }
+
+    /// Returns the id of the point nearest to point, `None` if the tree is empty.
+    pub fn nearest(&self, point: &Point) -> Option<u32> {
+        self.nearest_k(point, 1).first().cloned()
+    }
+
+    /// Returns the ids of the k points nearest to point, nearest first.
+    pub fn nearest_k(&self, point: &Point, k: usize) -> Vec<u32> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        // the k nearest points so far, sorted by their squared chord
+        let mut nearest: Vec<(f64, u32)> = Vec::with_capacity(k + 1);
+        self.search(
+            0,
+            self.ids.len(),
+            0,
+            &query(point),
+            f64::INFINITY,
+            &mut |d, id| {
+                let index = nearest.partition_point(|&(other, _)| other <= d);
+                nearest.insert(index, (d, id));
+                nearest.truncate(k);
+                if nearest.len() < k {
+                    f64::INFINITY
+                } else {
+                    nearest[k - 1].0
+                }
+            },
+        );
+
+        nearest.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Returns the ids of all points at most meters away from point, nearest first.
+    pub fn within_distance(&self, point: &Point, meters: f64) -> Vec<u32> {
+        let angle = meters_to_radians(meters).min(PI);
+        let chord = 2.0 * (angle / 2.0).sin();
+        let bound = chord * chord;
+
+        let mut within: Vec<(f64, u32)> = Vec::new();
+        self.search(0, self.ids.len(), 0, &query(point), bound, &mut |d, id| {
+            within.push((d, id));
+            bound
+        });
+
+        within.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        within.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Visits every entry in low..high whose squared chord to the query is at most bound. Visit
+    /// returns the bound for the remaining search, subtrees farther away than it are skipped.
+    fn search(
+        &self,
+        low: usize,
+        high: usize,
+        axis: usize,
+        query: &[f64; 3],
+        mut bound: f64,
+        visit: &mut impl FnMut(f64, u32) -> f64,
+    ) -> f64 {
+        if low >= high {
+            return bound;
+        }
+        let middle = low + (high - low) / 2;
+        let n_vector = &self.n_vectors[middle];
+
+        let d = squared_distance(n_vector, query);
+        if d <= bound {
+            bound = visit(d, self.ids[middle]);
+        }
+
+        let next_axis = (axis + 1) % 3;
+        let offset = query[axis] - n_vector[axis];
+        let (near, far) = if offset < 0.0 {
+            ((low, middle), (middle + 1, high))
+        } else {
+            ((middle + 1, high), (low, middle))
+        };
+        bound = self.search(near.0, near.1, next_axis, query, bound, visit);
+        if offset * offset <= bound {
+            bound = self.search(far.0, far.1, next_axis, query, bound, visit);
+        }
+        bound
+    }
+}
+
+fn build(entries: &mut [(u32, [f64; 3])], axis: usize) {
+    if entries.len() <= 1 {
+        return;
+    }
+    let middle = entries.len() / 2;
+    entries.select_nth_unstable_by(middle, |a, b| a.1[axis].total_cmp(&b.1[axis]));
+    let (below, above) = entries.split_at_mut(middle);
+    build(below, (axis + 1) % 3);
+    build(&mut above[1..], (axis + 1) % 3);
+}
+
+fn query(point: &Point) -> [f64; 3] {
+    let n_vector = point.n_vector();
+    [n_vector.x, n_vector.y, n_vector.z]
+}
+
+fn squared_distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::{
+        geometry::{
+            arc::Arc,
+            point::{radians_to_meter, Point},
+        },
+        spatial_partition::kd_tree::KdTree,
+    };
+
+    #[test]
+    fn same_as_linear_scan() {
+        let points: Vec<_> = Point::random_generator().take(2_000).collect();
+        let kd_tree = KdTree::new(&points);
+        assert_eq!(kd_tree.len(), points.len());
+        assert!(kd_tree.is_built_from(points.as_slice()));
+        assert!(!kd_tree.is_built_from(&points[1..]));
+        assert!(!kd_tree.is_built_from([&points[1..], &points[..1]].concat().as_slice()));
+
+        for query in Point::random_generator().take(50) {
+            let mut by_distance: Vec<_> = points
+                .iter()
+                .enumerate()
+                .map(|(id, point)| (Arc::new(point, &query).central_angle(), id as u32))
+                .collect();
+            by_distance.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+            assert_eq!(kd_tree.nearest(&query), Some(by_distance[0].1));
+            let nearest: Vec<_> = by_distance.iter().take(10).map(|&(_, id)| id).collect();
+            assert_eq!(kd_tree.nearest_k(&query, 10), nearest);
+
+            // far beyond the 30 km of the grid, the result is not cut off
+            let meters = 1_000_000.0;
+            let within: Vec<_> = by_distance
+                .iter()
+                .take_while(|&&(angle, _)| radians_to_meter(angle) <= meters)
+                .map(|&(_, id)| id)
+                .collect();
+            assert_eq!(kd_tree.within_distance(&query, meters), within);
+        }
+
+        assert_eq!(kd_tree.nearest_k(&points[0], 0), Vec::<u32>::new());
+        assert_eq!(kd_tree.within_distance(&points[0], 1e9).len(), points.len());
+        assert_eq!(KdTree::new(&[]).nearest(&points[0]), None);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod kd_tree;
 pub mod point_spatial_partition;
 pub mod polygon_spatial_partition;
 pub mod tiling;
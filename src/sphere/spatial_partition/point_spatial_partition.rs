@@ -1,4 +1,4 @@
-use std::f64::consts::PI;
+use std::{cmp::Ordering, collections::BinaryHeap};
 
 use indicatif::ProgressIterator;
 
@@ -117,24 +117,151 @@ impl PointSpatialPartition {
         points
     }
 
+    /// Returns the nearest point, `None` if there are no points.
     pub fn get_nearest(&self, point: &Point) -> Option<Point> {
-        let radius = 30_000.0;
-        let polygon = ConvecQuadrilateral::new(&vec![
-            Point::destination_point(point, 0.0 / 4.0 * PI, meters_to_radians(radius)),
-            Point::destination_point(point, 7.0 / 4.0 * PI, meters_to_radians(radius)),
-            Point::destination_point(point, 6.0 / 4.0 * PI, meters_to_radians(radius)),
-            Point::destination_point(point, 5.0 / 4.0 * PI, meters_to_radians(radius)),
-            Point::destination_point(point, 4.0 / 4.0 * PI, meters_to_radians(radius)),
-            Point::destination_point(point, 3.0 / 4.0 * PI, meters_to_radians(radius)),
-            Point::destination_point(point, 2.0 / 4.0 * PI, meters_to_radians(radius)),
-            Point::destination_point(point, 1.0 / 4.0 * PI, meters_to_radians(radius)),
-            Point::destination_point(point, 0.0 / 4.0 * PI, meters_to_radians(radius)),
-        ]);
-        let points = self.get_points(&polygon);
-        points.into_iter().min_by(|a, b| {
-            let a = Arc::new(a, point);
-            let b = Arc::new(b, point);
-            a.central_angle().partial_cmp(&b.central_angle()).unwrap()
-        })
+        self.get_k_nearest(point, 1).pop()
+    }
+
+    /// Returns the k points nearest to point, nearest first.
+    ///
+    /// The cells are visited best first, ordered by the distance between point and their
+    /// boundary. As no point of a cell is nearer than its boundary, a point taken from the queue
+    /// is nearer than every point not yet taken.
+    pub fn get_k_nearest(&self, point: &Point, k: usize) -> Vec<Point> {
+        let mut nearest = Vec::new();
+        let mut queue = BinaryHeap::new();
+        queue.push(Candidate {
+            distance: 0.0,
+            entry: Entry::Cell(self),
+        });
+
+        while let Some(candidate) = queue.pop() {
+            if nearest.len() >= k {
+                break;
+            }
+            match candidate.entry {
+                Entry::Point(found) => nearest.push(found),
+                Entry::Cell(cell) => match &cell.node_type {
+                    PointNodeType::Leaf(points) => {
+                        queue.extend(points.iter().map(|&other| Candidate {
+                            distance: Arc::new(&other, point).central_angle(),
+                            entry: Entry::Point(other),
+                        }))
+                    }
+                    PointNodeType::Internal(childs) => {
+                        queue.extend(childs.iter().map(|child| Candidate {
+                            distance: child.boundary.distance_to(point),
+                            entry: Entry::Cell(child),
+                        }))
+                    }
+                },
+            }
+        }
+
+        nearest
+    }
+
+    /// Returns all points at most meters away from point, nearest first.
+    pub fn within_distance(&self, point: &Point, meters: f64) -> Vec<Point> {
+        let radius = meters_to_radians(meters);
+        let mut points = Vec::new();
+        let mut internals = vec![self];
+        while let Some(parent) = internals.pop() {
+            if let PointNodeType::Leaf(leaf_points) = &parent.node_type {
+                points.extend(leaf_points.iter().filter_map(|&other| {
+                    let distance = Arc::new(&other, point).central_angle();
+                    (distance <= radius).then_some((distance, other))
+                }));
+            } else if let PointNodeType::Internal(childs) = &parent.node_type {
+                internals.extend(
+                    childs
+                        .iter()
+                        .filter(|child| child.boundary.distance_to(point) <= radius),
+                );
+            }
+        }
+
+        points.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        points.into_iter().map(|(_, point)| point).collect()
+    }
+}
+
+enum Entry<'a> {
+    Cell(&'a PointSpatialPartition),
+    Point(Point),
+}
+
+/// An entry of the best first search, ordered so that the nearest entry is on top of the heap.
+struct Candidate<'a> {
+    distance: f64,
+    entry: Entry<'a>,
+}
+
+impl PartialEq for Candidate<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate<'_> {}
+
+impl PartialOrd for Candidate<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::{
+        geometry::{
+            arc::Arc,
+            point::{radians_to_meter, Point},
+        },
+        spatial_partition::point_spatial_partition::PointSpatialPartition,
+    };
+
+    #[test]
+    fn same_as_linear_scan() {
+        let points: Vec<_> = Point::random_generator().take(2_000).collect();
+        let mut point_grid = PointSpatialPartition::new_root(10);
+        points.iter().for_each(|point| point_grid.add_point(point));
+
+        for query in Point::random_generator().take(20) {
+            let mut by_distance = points.clone();
+            by_distance.sort_unstable_by(|a, b| {
+                let a = Arc::new(a, &query).central_angle();
+                let b = Arc::new(b, &query).central_angle();
+                a.total_cmp(&b)
+            });
+
+            // the points are about 500 km apart, far beyond the 30 km of the old search
+            assert!(point_grid.get_nearest(&query) == Some(by_distance[0]));
+            assert!(point_grid.get_k_nearest(&query, 10) == by_distance[..10]);
+
+            let meters = 1_000_000.0;
+            let within: Vec<_> = by_distance
+                .iter()
+                .cloned()
+                .take_while(|point| {
+                    radians_to_meter(Arc::new(point, &query).central_angle()) <= meters
+                })
+                .collect();
+            assert!(point_grid.within_distance(&query, meters) == within);
+        }
+
+        assert_eq!(
+            point_grid.get_k_nearest(&points[0], 3_000).len(),
+            points.len()
+        );
+        assert!(PointSpatialPartition::new_root(10)
+            .get_nearest(&points[0])
+            .is_none());
     }
 }
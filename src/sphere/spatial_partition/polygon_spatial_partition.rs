@@ -1,9 +1,10 @@
 use indicatif::ProgressIterator;
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::sphere::geometry::{
     arc::Arc,
     collision_detection::{Collides, CollisionDetection, Contains},
-    point::{meters_to_radians, Point},
+    point::{meters_to_radians, radians_to_meter, Point},
     polygon::Polygon,
 };
 
@@ -16,6 +17,8 @@ pub struct PolygonSpatialPartition {
     max_size: usize,
     midpoint: Point,
     midpoint_flag: PointStatus,
+    /// Seed of the random midpoint of this cell, see `child_seed`.
+    seed: u64,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -46,7 +49,14 @@ impl CollisionDetection for PolygonSpatialPartition {
 }
 
 impl PolygonSpatialPartition {
+    /// Creates an empty partition, the midpoints of the cells are chosen at random.
     pub fn new(max_size: usize) -> PolygonSpatialPartition {
+        PolygonSpatialPartition::with_seed(max_size, rand::random())
+    }
+
+    /// Creates an empty partition whose midpoints, and therefore the classification of points as
+    /// on land, are the same for the same seed and polygons.
+    pub fn with_seed(max_size: usize, seed: u64) -> PolygonSpatialPartition {
         let boundary = ConvecQuadrilateral::new(&vec![
             Point::from_coordinate(0.0, 0.0),
             Point::from_coordinate(1.0, 1.0),
@@ -54,30 +64,39 @@ impl PolygonSpatialPartition {
             Point::from_coordinate(1.0, -1.0),
             Point::from_coordinate(0.0, 0.0),
         ]);
-        let midpoint = boundary.get_midpoint();
+        let midpoint = boundary.get_midpoint(&mut StdRng::seed_from_u64(seed));
         PolygonSpatialPartition {
             boundary,
             node_type: NodeType::Internal(
                 Tiling::base_tiling()
                     .iter()
                     .cloned()
-                    .map(|p| PolygonSpatialPartition::new_leaf(p, max_size))
+                    .enumerate()
+                    .map(|(i, p)| {
+                        PolygonSpatialPartition::new_leaf(p, max_size, child_seed(seed, i))
+                    })
                     .collect(),
             ),
             max_size,
             midpoint,
             midpoint_flag: PointStatus::Outside,
+            seed,
         }
     }
 
-    fn new_leaf(boundary: ConvecQuadrilateral, max_size: usize) -> PolygonSpatialPartition {
-        let midpoint = boundary.get_midpoint();
+    fn new_leaf(
+        boundary: ConvecQuadrilateral,
+        max_size: usize,
+        seed: u64,
+    ) -> PolygonSpatialPartition {
+        let midpoint = boundary.get_midpoint(&mut StdRng::seed_from_u64(seed));
         PolygonSpatialPartition {
             boundary,
             node_type: NodeType::Leaf(Vec::with_capacity(max_size + 1)),
             max_size,
             midpoint,
             midpoint_flag: PointStatus::Outside,
+            seed,
         }
     }
 
@@ -110,7 +129,14 @@ impl PolygonSpatialPartition {
             self.boundary
                 .split()
                 .into_iter()
-                .map(|rectangle| PolygonSpatialPartition::new_leaf(rectangle, self.max_size))
+                .enumerate()
+                .map(|(i, rectangle)| {
+                    PolygonSpatialPartition::new_leaf(
+                        rectangle,
+                        self.max_size,
+                        child_seed(self.seed, i),
+                    )
+                })
                 .collect(),
         );
 
@@ -224,25 +250,88 @@ impl PolygonSpatialPartition {
     }
 
     pub fn is_on_polygon(&self, point: &Point) -> bool {
+        let (leaf, arcs) = self.leaf(point);
+        let ray = Arc::new(point, &leaf.midpoint);
+        let intersections = arcs.iter().filter_map(|arc| ray.intersection(arc)).count();
+        (intersections % 2 == 0) == (leaf.midpoint_flag == PointStatus::Inside)
+    }
+
+    /// Returns the number of polygon arcs per square meter in the leaf containing point. The
+    /// leaves split when they fill up, so the density is high near long and winding coastlines
+    /// and zero on the open sea.
+    pub fn arc_density(&self, point: &Point) -> f64 {
+        let (leaf, arcs) = self.leaf(point);
+        let square_meters = leaf.boundary.area() * radians_to_meter(1.0).powi(2);
+        arcs.len() as f64 / square_meters
+    }
+
+    /// Returns the leaf containing point together with its arcs.
+    fn leaf(&self, point: &Point) -> (&PolygonSpatialPartition, &[Arc]) {
         let mut current = self;
         loop {
             match &current.node_type {
                 NodeType::Internal(childs) => {
-                    for child in childs {
-                        let contrains = child.boundary.contains(point);
-                        if contrains {
-                            current = &child;
-                            break;
-                        }
-                    }
-                }
-                NodeType::Leaf(arcs) => {
-                    let ray = Arc::new(point, &current.midpoint);
-                    let intersections = arcs.iter().filter_map(|arc| ray.intersection(arc)).count();
-                    return (intersections % 2 == 0)
-                        == (current.midpoint_flag == PointStatus::Inside);
+                    // a point exactly on the border between children, e.g. on a meridian of the
+                    // base tiling, is contained in none of them
+                    current = childs
+                        .iter()
+                        .find(|child| child.boundary.contains(point))
+                        .unwrap_or_else(|| {
+                            childs
+                                .iter()
+                                .min_by(|x, y| {
+                                    x.boundary
+                                        .distance_to(point)
+                                        .total_cmp(&y.boundary.distance_to(point))
+                                })
+                                .unwrap()
+                        });
                 }
+                NodeType::Leaf(arcs) => return (current, arcs),
             }
         }
     }
 }
+
+/// Returns the seed of the i-th child of the cell with seed, mixed with the SplitMix64 finalizer.
+/// The seeds do not depend on the order in which the cells are split.
+fn child_seed(seed: u64, i: usize) -> u64 {
+    let mut z = seed.wrapping_add((i as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sphere::{
+        geometry::{point::Point, polygon::Polygon},
+        spatial_partition::polygon_spatial_partition::PolygonSpatialPartition,
+    };
+
+    #[test]
+    fn points_on_cell_borders() {
+        let island = Polygon::new(vec![
+            Point::from_coordinate(0.0, 10.0),
+            Point::from_coordinate(0.0, 11.0),
+            Point::from_coordinate(1.0, 11.0),
+            Point::from_coordinate(1.0, 10.0),
+            Point::from_coordinate(0.0, 10.0),
+        ]);
+        let mut planet_grid = PolygonSpatialPartition::with_seed(10, 0);
+        planet_grid.add_polygons(&vec![island]);
+
+        assert!(planet_grid.is_on_polygon(&Point::from_coordinate(0.5, 10.5)));
+        assert!(!planet_grid.is_on_polygon(&Point::from_coordinate(5.0, 10.5)));
+        assert!(planet_grid.arc_density(&Point::from_coordinate(0.5, 10.5)) > 0.0);
+        assert_eq!(
+            planet_grid.arc_density(&Point::from_coordinate(-45.0, -90.0)),
+            0.0
+        );
+
+        // on the meridians and the equator bounding the cells of the base tiling
+        for (latitude, longitude) in [(45.0, 90.0), (-30.0, 0.0), (0.0, -135.0), (10.0, 180.0)] {
+            assert!(!planet_grid.is_on_polygon(&Point::from_coordinate(latitude, longitude)));
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use crate::sphere::geometry::{arc::Arc, point::Point};
+use rand::Rng;
+
+use crate::sphere::geometry::{arc::Arc, collision_detection::Contains, point::Point};
 
 #[derive(Clone)]
 pub struct ConvecQuadrilateral {
@@ -46,12 +48,12 @@ impl ConvecQuadrilateral {
         }
     }
 
-    pub fn get_midpoint(&self) -> Point {
+    pub fn get_midpoint(&self, rng: &mut impl Rng) -> Point {
         for _ in 0..5 {
-            let m0 = Arc::new(&self.outline[0], &self.outline[1]).random_intermediate_point();
-            let m1 = Arc::new(&self.outline[1], &self.outline[2]).random_intermediate_point();
-            let m2 = Arc::new(&self.outline[2], &self.outline[3]).random_intermediate_point();
-            let m3 = Arc::new(&self.outline[3], &self.outline[4]).random_intermediate_point();
+            let m0 = Arc::new(&self.outline[0], &self.outline[1]).random_intermediate_point(rng);
+            let m1 = Arc::new(&self.outline[1], &self.outline[2]).random_intermediate_point(rng);
+            let m2 = Arc::new(&self.outline[2], &self.outline[3]).random_intermediate_point(rng);
+            let m3 = Arc::new(&self.outline[3], &self.outline[4]).random_intermediate_point(rng);
             let d0 = Arc::new(&m0, &m2);
             let d1 = Arc::new(&m1, &m3);
             if let Some(intersection) = d0.intersection(&d1) {
@@ -61,6 +63,24 @@ impl ConvecQuadrilateral {
         panic!("no midpoint found :(");
     }
 
+    /// Returns the area of the quadrilateral in steradians, that is on the unit sphere.
+    pub fn area(&self) -> f64 {
+        let o = &self.outline;
+        spherical_triangle_area(&o[0], &o[1], &o[2]) + spherical_triangle_area(&o[0], &o[2], &o[3])
+    }
+
+    /// Returns the central angle between point and the nearest point of the quadrilateral in
+    /// radians, zero if the point lies inside.
+    pub fn distance_to(&self, point: &Point) -> f64 {
+        if self.contains(point) {
+            return 0.0;
+        }
+        self.outline
+            .windows(2)
+            .map(|outline| Arc::new(&outline[0], &outline[1]).distance_to(point))
+            .fold(f64::INFINITY, f64::min)
+    }
+
     pub fn intersections(&self, line: &Arc) -> Vec<Point> {
         self.outline
             .windows(2)
@@ -130,3 +150,34 @@ impl Tiling {
         base_pixels
     }
 }
+
+/// Returns the area of the spherical triangle in steradians, by the formula of Van Oosterom and
+/// Strackee.
+fn spherical_triangle_area(a: &Point, b: &Point, c: &Point) -> f64 {
+    // the n-vectors of e.g. `Arc::middle` are not normalized
+    let (a, b, c) = (
+        a.n_vector().normalize(),
+        b.n_vector().normalize(),
+        c.n_vector().normalize(),
+    );
+    let numerator = a.dot(&b.cross(&c)).abs();
+    let denominator = 1.0 + a.dot(&b) + b.dot(&c) + c.dot(&a);
+    2.0 * numerator.atan2(denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::sphere::spatial_partition::tiling::Tiling;
+
+    #[test]
+    fn base_tiling_covers_sphere() {
+        let area: f64 = Tiling::base_tiling().iter().map(|tile| tile.area()).sum();
+        assert!((area - 4.0 * PI).abs() < 1e-9, "{}", area);
+
+        let tile = &Tiling::base_tiling()[0];
+        let area: f64 = tile.split().iter().map(|sub| sub.area()).sum();
+        assert!((area - tile.area()).abs() < 1e-9, "{}", area);
+    }
+}